@@ -0,0 +1,284 @@
+use super::interpreter::*;
+use super::functions::*;
+use super::errors::*;
+
+fn expect_array(name: &str, value: CreateAny) -> Result<Array, CreateError> {
+    match value {
+        CreateAny::ARR(a) => Ok(a),
+        v => Err(CreateError { code: 10, message: format!("{} expected an array, found {:?}", name, v.get_type()) }),
+    }
+}
+
+fn expect_index(name: &str, value: CreateAny, len: usize, bound: usize) -> Result<usize, CreateError> {
+    match value {
+        CreateAny::BUF(b) => resolve_offset(b, len, bound, &format!("the array given to {}", name)),
+        v => Err(CreateError { code: 10, message: format!("{} expected a buffer index, found {:?}", name, v.get_type()) }),
+    }
+}
+
+pub fn array_len(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("len", values.next().unwrap())?;
+    Ok(CreateAny::BUF(arr.len() as Buffer))
+}
+
+pub fn array_push(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("push", values.next().unwrap())?;
+    arr.push(values.next().unwrap());
+    Ok(CreateAny::ARR(arr))
+}
+
+pub fn array_push_front(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("pushf", values.next().unwrap())?;
+    arr.insert(0, values.next().unwrap());
+    Ok(CreateAny::ARR(arr))
+}
+
+pub fn array_pop(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("pop", values.next().unwrap())?;
+    match arr.pop() {
+        Some(value) => Ok(CreateAny::ARR(vec![value, CreateAny::ARR(arr)])),
+        None => Err(CreateError { code: 4, message: "Tried to pop from an empty array".to_string() }),
+    }
+}
+
+pub fn array_pop_front(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("popf", values.next().unwrap())?;
+    if arr.is_empty() {
+        return Err(CreateError { code: 4, message: "Tried to pop from the front of an empty array".to_string() });
+    }
+    let value = arr.remove(0);
+    Ok(CreateAny::ARR(vec![value, CreateAny::ARR(arr)]))
+}
+
+pub fn array_insert(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("insert", values.next().unwrap())?;
+    let index = expect_index("insert", values.next().unwrap(), arr.len(), arr.len() + 1)?;
+    arr.insert(index, values.next().unwrap());
+    Ok(CreateAny::ARR(arr))
+}
+
+pub fn array_remove(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("remove", values.next().unwrap())?;
+    if arr.is_empty() {
+        return Err(CreateError { code: 4, message: "Tried to remove from an empty array".to_string() });
+    }
+    let index = expect_index("remove", values.next().unwrap(), arr.len(), arr.len())?;
+    arr.remove(index);
+    Ok(CreateAny::ARR(arr))
+}
+
+pub fn array_slice(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("slice", values.next().unwrap())?;
+    let start = expect_index("slice", values.next().unwrap(), arr.len(), arr.len() + 1)?;
+    let end = expect_index("slice", values.next().unwrap(), arr.len(), arr.len() + 1)?;
+    if start > end {
+        return Err(CreateError { code: 4, message: format!("Slice start {} was after slice end {}", start, end) });
+    }
+    Ok(CreateAny::ARR(arr[start..end].to_vec()))
+}
+
+pub fn array_concat(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("concat", values.next().unwrap())?;
+    arr.extend(expect_array("concat", values.next().unwrap())?);
+    Ok(CreateAny::ARR(arr))
+}
+
+pub fn array_reverse(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("reverse", values.next().unwrap())?;
+    arr.reverse();
+    Ok(CreateAny::ARR(arr))
+}
+
+pub fn array_index_of(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("indexof", values.next().unwrap())?;
+    let value = values.next().unwrap();
+    Ok(CreateAny::BUF(match arr.iter().position(|v| *v == value) {
+        Some(i) => i as Buffer,
+        None => -1.,
+    }))
+}
+
+pub fn array_contains(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("contains", values.next().unwrap())?;
+    let value = values.next().unwrap();
+    Ok(CreateAny::BUF(if arr.contains(&value) {1.} else {0.}))
+}
+
+pub fn array_sort(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("sort", values.next().unwrap())?;
+    let mut buffers = arr.into_iter()
+        .map(|v| match v {
+            CreateAny::BUF(b) => Ok(b),
+            v => Err(CreateError { code: 10, message: format!("sort can only sort buffers, found {:?}", v.get_type()) }),
+        })
+        .collect::<Result<Vec<Buffer>, CreateError>>()?;
+    buffers.sort_by(|a, b| a.total_cmp(b));
+    Ok(CreateAny::ARR(buffers.into_iter().map(CreateAny::BUF).collect()))
+}
+
+pub fn range_length(start: Buffer, stop: Buffer, step: Buffer) -> Result<usize, CreateError> {
+    if step == 0. {
+        return Err(CreateError { code: 10, message: "Range step cannot be zero".to_string() });
+    }
+    if !start.is_finite() || !stop.is_finite() || !step.is_finite() {
+        return Err(CreateError { code: 10, message: format!("Range from {} to {} by {} must be finite", start, stop, step) });
+    }
+    Ok(((stop as f64 - start as f64) / step as f64).ceil().max(0.) as usize)
+}
+
+pub fn range_value(start: Buffer, step: Buffer, index: usize) -> Buffer {
+    (start as f64 + index as f64 * step as f64) as Buffer
+}
+
+pub fn array_range(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let bounds = values.into_iter()
+        .map(|v| match v {
+            CreateAny::BUF(b) => Ok(b),
+            v => Err(CreateError { code: 10, message: format!("range expected buffers, found {:?}", v.get_type()) }),
+        })
+        .collect::<Result<Vec<Buffer>, CreateError>>()?;
+    let (start, stop, step) = (bounds[0], bounds[1], bounds[2]);
+    let length = range_length(start, stop, step)?;
+    Ok(CreateAny::ARR((0..length).map(|i| CreateAny::BUF(range_value(start, step, i))).collect()))
+}
+
+fn expect_function(name: &str, value: CreateAny) -> Result<Function, CreateError> {
+    match value {
+        CreateAny::FUN(f) => Ok(f),
+        v => Err(CreateError { code: 10, message: format!("{} expected a function, found {:?}", name, v.get_type()) }),
+    }
+}
+
+fn call_predicate(name: &str, function: &Function, mut args: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<bool, CreateError> {
+    match function.evaluate(&mut args, environment, lossy)? {
+        CreateAny::BUF(b) => Ok(is_truthy(b)),
+        v => Err(CreateError { code: 10, message: format!("{} expected its function to return a buffer, found {:?}", name, v.get_type()) }),
+    }
+}
+
+pub fn array_map(values: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("map", values.next().unwrap())?;
+    let function = expect_function("map", values.next().unwrap())?;
+    let mut mapped = Array::new();
+    for value in arr {
+        mapped.push(function.evaluate(&mut vec![value], environment, lossy)?);
+    }
+    Ok(CreateAny::ARR(mapped))
+}
+
+pub fn array_filter(values: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("filter", values.next().unwrap())?;
+    let function = expect_function("filter", values.next().unwrap())?;
+    let mut filtered = Array::new();
+    for value in arr {
+        if call_predicate("filter", &function, vec![value.clone()], environment, lossy)? {
+            filtered.push(value);
+        }
+    }
+    Ok(CreateAny::ARR(filtered))
+}
+
+pub fn array_fold(values: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("fold", values.next().unwrap())?;
+    let mut accumulator = values.next().unwrap();
+    let function = expect_function("fold", values.next().unwrap())?;
+    for value in arr {
+        accumulator = function.evaluate(&mut vec![accumulator, value], environment, lossy)?;
+    }
+    Ok(accumulator)
+}
+
+pub fn array_reduce(values: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let mut arr = expect_array("reduce", values.next().unwrap())?.into_iter();
+    let function = expect_function("reduce", values.next().unwrap())?;
+    let mut accumulator = match arr.next() {
+        Some(v) => v,
+        None => return Err(CreateError { code: 4, message: "Tried to reduce an empty array".to_string() }),
+    };
+    for value in arr {
+        accumulator = function.evaluate(&mut vec![accumulator, value], environment, lossy)?;
+    }
+    Ok(accumulator)
+}
+
+pub fn array_any(values: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("any", values.next().unwrap())?;
+    let function = expect_function("any", values.next().unwrap())?;
+    for value in arr {
+        if call_predicate("any", &function, vec![value], environment, lossy)? {
+            return Ok(CreateAny::BUF(1.));
+        }
+    }
+    Ok(CreateAny::BUF(0.))
+}
+
+pub fn array_all(values: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("all", values.next().unwrap())?;
+    let function = expect_function("all", values.next().unwrap())?;
+    for value in arr {
+        if !call_predicate("all", &function, vec![value], environment, lossy)? {
+            return Ok(CreateAny::BUF(0.));
+        }
+    }
+    Ok(CreateAny::BUF(1.))
+}
+
+pub fn array_find(values: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("find", values.next().unwrap())?;
+    let function = expect_function("find", values.next().unwrap())?;
+    for value in arr {
+        if call_predicate("find", &function, vec![value.clone()], environment, lossy)? {
+            return Ok(value);
+        }
+    }
+    Ok(CreateAny::NUL())
+}
+
+pub fn array_sort_by(values: Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let arr = expect_array("sortby", values.next().unwrap())?;
+    let function = expect_function("sortby", values.next().unwrap())?;
+    Ok(CreateAny::ARR(merge_sort(arr, &function, environment, lossy)?))
+}
+
+fn merge_sort(mut left: Array, function: &Function, environment: &mut Environment, lossy: bool) -> Result<Array, CreateError> {
+    if left.len() <= 1 {return Ok(left)}
+    let right = left.split_off(left.len() / 2);
+    let mut left = merge_sort(left, function, environment, lossy)?.into_iter().peekable();
+    let mut right = merge_sort(right, function, environment, lossy)?.into_iter().peekable();
+    let mut merged = Array::new();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        let order = match function.evaluate(&mut vec![l.clone(), r.clone()], environment, lossy)? {
+            CreateAny::BUF(b) => b,
+            v => return Err(CreateError { code: 10, message: format!("sortby expected its comparator to return a buffer, found {:?}", v.get_type()) }),
+        };
+        if order <= 0. {
+            merged.push(left.next().unwrap());
+        } else {
+            merged.push(right.next().unwrap());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
@@ -2,6 +2,9 @@ use std::rc::Rc;
 use std::cell::RefCell;
 use crate::lib::interpreter::*;
 use crate::lib::errors::*;
+use crate::lib::arrays::*;
+use crate::lib::runtime::*;
+use crate::lib::output::*;
 
 pub struct If {
     condition: MutableBuffer,
@@ -14,10 +17,10 @@ impl Controller for If {
             CreateResult::Ok() => (),
             CreateResult::Err(e) => return CreateResult::Err(e),
         }
-        if match environment.buffers.get_buf(0) {
+        if is_truthy(match environment.buffers.get_buf(0) {
             Some(b) => *b,
             None => return CreateResult::Err(CreateError { code: 5, message: "If condition did not return buffer, and no buffers were found.".to_string() })
-        } != 0. {
+        }) {
             self.mutbuffer.evaluate(environment, lossy)
         } else {
             CreateResult::Ok()
@@ -47,10 +50,10 @@ impl Controller for IfElse {
             CreateResult::Ok() => (),
             CreateResult::Err(e) => return CreateResult::Err(e),
         }
-        if match environment.buffers.get_buf(0) {
+        if is_truthy(match environment.buffers.get_buf(0) {
             Some(b) => *b,
             None => return CreateResult::Err(CreateError { code: 3, message: "IfElse condition did not return a buffer, and no buffer was found.".to_string() }),
-        } != 0. {
+        }) {
             self.ifmutbuffer.evaluate(environment, lossy)
         } else {
             self.elsemutbuffer.evaluate(environment, lossy)
@@ -71,7 +74,10 @@ impl IfElse {
 pub struct For {
     times: MutableBuffer,
     identifier: Option<String>,
+    start: Option<MutableBuffer>,
+    step: Option<MutableBuffer>,
     mutbuffer: MutableBuffer,
+    label: Option<String>,
 }
 
 impl Controller for For {
@@ -80,21 +86,38 @@ impl Controller for For {
             CreateResult::Ok() => (),
             CreateResult::Err(e) => return CreateResult::Err(e),
         }
-        let mut scope = Scope::new(environment.scope);
-        let iter_num = match environment.buffers.get_buf(0) {
-            Some(b) => b,
+        let stop = match environment.buffers.get_buf(0) {
+            Some(b) => *b,
             None => return CreateResult::Err(CreateError { code: 3, message: "For loop condition did not return value, and no value was found in buffer".to_string() }),
-        }.trunc() as i32;
-        let mut env = Environment { buffers: environment.buffers, writers: environment.writers, scope: &mut scope };
+        };
+        let (start, step, iter_num) = if self.start.is_none() && self.step.is_none() {
+            (0., 1., stop.trunc().max(0.) as usize)
+        } else {
+            let start = match read_bound(&self.start, 0., environment, lossy) {
+                Ok(v) => v,
+                Err(e) => return CreateResult::Err(e),
+            };
+            let step = match read_bound(&self.step, 1., environment, lossy) {
+                Ok(v) => v,
+                Err(e) => return CreateResult::Err(e),
+            };
+            match range_length(start, stop, step) {
+                Ok(n) => (start, step, n),
+                Err(e) => return CreateResult::Err(e),
+            }
+        };
+        let mut scope = Scope::new(environment.scope);
+        let mut env = Environment { buffers: environment.buffers, writers: environment.writers, scope: &mut scope, runtime: environment.runtime };
         for num in 0..iter_num {
             match &self.identifier {
-                Some(i) => {env.scope.insert_locally(i.clone(), CreateAny::BUF(num as Buffer));},
+                Some(i) => {env.scope.insert_locally(i.clone(), CreateAny::BUF(range_value(start, step, num)));},
                 None => (),
             };
             match self.mutbuffer.clone().evaluate_clone(&mut env, false) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => match e.code {
-                    11 => break,
+                    11 if claim_signal(&self.label, env.runtime) => break,
+                    23 if claim_signal(&self.label, env.runtime) => (),
                     _ => return CreateResult::Err(e),
                 }
             };
@@ -103,13 +126,36 @@ impl Controller for For {
     }
 
     fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
-        Rc::new(RefCell::new(For::new(self.times.clone(), self.identifier.clone(), self.mutbuffer.clone())))
+        Rc::new(RefCell::new(For::new(self.times.clone(), self.identifier.clone(), self.start.clone(), self.step.clone(), self.mutbuffer.clone(), self.label.clone())))
     }
 }
 
 impl For {
-    pub fn new(times: MutableBuffer, identifier: Option<String>, mutbuffer: MutableBuffer) -> Self {
-        For { times, identifier, mutbuffer }
+    pub fn new(times: MutableBuffer, identifier: Option<String>, start: Option<MutableBuffer>, step: Option<MutableBuffer>, mutbuffer: MutableBuffer, label: Option<String>) -> Self {
+        For { times, identifier, start, step, mutbuffer, label }
+    }
+}
+
+fn read_bound(mutbuffer: &Option<MutableBuffer>, default: Buffer, environment: &mut Environment, lossy: bool) -> Result<Buffer, CreateError> {
+    match mutbuffer {
+        Some(m) => match m.eval_clone_return(environment, lossy)? {
+            Some(v) => match *v {
+                CreateAny::BUF(b) => Ok(b),
+                _ => Err(CreateError { code: 9, message: "For loop start and step must be buffers".to_string() }),
+            },
+            None => Err(CreateError { code: 9, message: "For loop start and step cannot be null".to_string() }),
+        },
+        None => Ok(default),
+    }
+}
+
+fn claim_signal(label: &Option<String>, runtime: &mut Runtime) -> bool {
+    match &runtime.loop_label {
+        Some(target) if Some(target) != label.as_ref() => false,
+        _ => {
+            runtime.loop_label = None;
+            true
+        },
     }
 }
 
@@ -117,6 +163,7 @@ pub struct ForIn {
     value: MutableBuffer,
     identifier: Option<String>,
     mutbuffer: MutableBuffer,
+    label: Option<String>,
 }
 
 impl Controller for ForIn {
@@ -130,7 +177,7 @@ impl Controller for ForIn {
             None => return CreateResult::Err(CreateError { code: 3, message: "ForIn loop condition did not return array, and array was found in buffer".to_string() })
         };
         let mut scope = Scope::new(environment.scope);
-        let mut env = Environment { buffers: environment.buffers, writers: environment.writers, scope: &mut scope };
+        let mut env = Environment { buffers: environment.buffers, writers: environment.writers, scope: &mut scope, runtime: environment.runtime };
         for value in array {
             match &self.identifier {
                 Some(i) => {env.scope.insert_locally(i.clone(), value);},
@@ -139,7 +186,8 @@ impl Controller for ForIn {
             match self.mutbuffer.clone().evaluate_clone(&mut env, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => match e.code {
-                    11 => break,
+                    11 if claim_signal(&self.label, env.runtime) => break,
+                    23 if claim_signal(&self.label, env.runtime) => (),
                     _ => return CreateResult::Err(e)
                 },
             }
@@ -149,36 +197,38 @@ impl Controller for ForIn {
     }
 
     fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
-        Rc::new(RefCell::new(ForIn::new(self.value.clone(), self.identifier.clone(), self.mutbuffer.clone())))
+        Rc::new(RefCell::new(ForIn::new(self.value.clone(), self.identifier.clone(), self.mutbuffer.clone(), self.label.clone())))
     }
 }
 
 impl ForIn {
-    pub fn new(value: MutableBuffer, identifier: Option<String>, mutbuffer: MutableBuffer) -> Self {
-        ForIn { value, identifier, mutbuffer }
+    pub fn new(value: MutableBuffer, identifier: Option<String>, mutbuffer: MutableBuffer, label: Option<String>) -> Self {
+        ForIn { value, identifier, mutbuffer, label }
     }
 }
 
 pub struct While {
     condition: MutableBuffer,
     mutbuffer: MutableBuffer,
+    label: Option<String>,
 }
 
 impl Controller for While {
     fn run(&mut self, environment: &mut Environment, _lossy: bool) -> CreateResult {
         
-        while match self.condition.clone().eval_clone_return(environment, false) {
+        while is_truthy(match self.condition.clone().eval_clone_return(environment, false) {
             Ok(Some(v)) => match *v {
                 CreateAny::BUF(b) => b,
                 _ => return CreateResult::Err(CreateError { code: 3, message: "While controller condition did not return a buffer".to_string() }),
             },
             Ok(None) => return CreateResult::Err(CreateError { code: 3, message: "While controller conditions cannot be null".to_string() }),
             Err(e) => return CreateResult::Err(e),
-        } != 0. {
+        }) {
             match self.mutbuffer.clone().evaluate_clone(environment, false) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => match e.code {
-                    11 => break,
+                    11 if claim_signal(&self.label, environment.runtime) => break,
+                    23 if claim_signal(&self.label, environment.runtime) => (),
                     _ => return CreateResult::Err(e),
                 }
             }
@@ -187,13 +237,224 @@ impl Controller for While {
     }
 
     fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
-        Rc::new(RefCell::new(While::new(self.condition.clone(), self.mutbuffer.clone())))
+        Rc::new(RefCell::new(While::new(self.condition.clone(), self.mutbuffer.clone(), self.label.clone())))
     }
 }
 
 impl While {
-    pub fn new(condition: MutableBuffer, mutbuffer: MutableBuffer) -> Self {
-        While { condition, mutbuffer }
+    pub fn new(condition: MutableBuffer, mutbuffer: MutableBuffer, label: Option<String>) -> Self {
+        While { condition, mutbuffer, label }
+    }
+}
+
+#[derive(Clone)]
+pub enum MatchPattern {
+    Value(MutableBuffer),
+    Range(MutableBuffer, MutableBuffer),
+    Type(CreateType),
+    Default,
+}
+
+#[derive(Clone)]
+pub struct Match {
+    value: MutableBuffer,
+    arms: Vec<(MatchPattern, MutableBuffer)>,
+}
+
+impl Controller for Match {
+    fn run(&mut self, environment: &mut Environment, lossy: bool) -> CreateResult {
+        let value = match self.value.eval_clone_return(environment, lossy) {
+            Ok(v) => v.map_or(CreateAny::NUL(), |v| *v),
+            Err(e) => return CreateResult::Err(e),
+        };
+        for (pattern, mutbuffer) in &self.arms {
+            let matched = match pattern {
+                MatchPattern::Value(m) => match m.eval_clone_return(environment, lossy) {
+                    Ok(v) => v.map_or(CreateAny::NUL(), |v| *v) == value,
+                    Err(e) => return CreateResult::Err(e),
+                },
+                MatchPattern::Range(low, high) => match (read_match_bound(low, environment, lossy), read_match_bound(high, environment, lossy), &value) {
+                    (Ok(l), Ok(h), CreateAny::BUF(v)) => l <= *v && *v <= h,
+                    (Err(e), _, _) | (_, Err(e), _) => return CreateResult::Err(e),
+                    _ => false,
+                },
+                MatchPattern::Type(t) => t.matches(&value),
+                MatchPattern::Default => true,
+            };
+            if matched {
+                return mutbuffer.clone().evaluate_clone(environment, lossy);
+            }
+        }
+        CreateResult::Ok()
+    }
+
+    fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
+        Rc::new(RefCell::new(self.clone()))
+    }
+}
+
+impl Match {
+    pub fn new(value: MutableBuffer, arms: Vec<(MatchPattern, MutableBuffer)>) -> Self {
+        Match { value, arms }
+    }
+
+    pub fn is_exhaustive(&self) -> bool {
+        use CreateType::*;
+        self.arms.iter().any(|(p, _)| matches!(p, MatchPattern::Default))
+            || [BUF, ARR, FUN, SCP, NUL].iter().all(|t| self.arms.iter().any(|(p, _)| matches!(p, MatchPattern::Type(u) if u == t)))
+    }
+}
+
+fn read_match_bound(mutbuffer: &MutableBuffer, environment: &mut Environment, lossy: bool) -> Result<Buffer, CreateError> {
+    match mutbuffer.eval_clone_return(environment, lossy)? {
+        Some(v) => match *v {
+            CreateAny::BUF(b) => Ok(b),
+            _ => Err(CreateError { code: 9, message: "Match range bounds must be buffers".to_string() }),
+        },
+        None => Err(CreateError { code: 9, message: "Match range bounds cannot be null".to_string() }),
+    }
+}
+
+#[derive(Clone)]
+pub struct Try {
+    mutbuffer: MutableBuffer,
+    identifier: Option<String>,
+    catchmutbuffer: Option<MutableBuffer>,
+    finallymutbuffer: Option<MutableBuffer>,
+}
+
+impl Controller for Try {
+    fn run(&mut self, environment: &mut Environment, lossy: bool) -> CreateResult {
+        let mut result = self.mutbuffer.clone().evaluate_clone(environment, lossy);
+        if let (CreateResult::Err(e), Some(catchmutbuffer)) = (&result, &self.catchmutbuffer) {
+            if is_catchable(e) {
+                let error = describe_error(e, environment.runtime);
+                let mut scope = Scope::new(environment.scope);
+                if let Some(i) = &self.identifier {
+                    scope.insert_locally(i.clone(), error);
+                }
+                let mut env = Environment { buffers: environment.buffers, writers: environment.writers, scope: &mut scope, runtime: environment.runtime };
+                result = catchmutbuffer.clone().evaluate_clone(&mut env, lossy);
+            }
+        }
+        if let Some(finallymutbuffer) = &self.finallymutbuffer {
+            // the finally body may return, break or throw internally, so set aside whatever the try left pending
+            let return_value = environment.runtime.return_value.take();
+            let loop_label = environment.runtime.loop_label.take();
+            let thrown = environment.runtime.thrown.take();
+            if let CreateResult::Err(e) = finallymutbuffer.clone().evaluate_clone(environment, lossy) {
+                return CreateResult::Err(e);
+            }
+            environment.runtime.return_value = return_value;
+            environment.runtime.loop_label = loop_label;
+            environment.runtime.thrown = thrown;
+        }
+        result
+    }
+
+    fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
+        Rc::new(RefCell::new(self.clone()))
+    }
+}
+
+impl Try {
+    pub fn new(mutbuffer: MutableBuffer, identifier: Option<String>, catchmutbuffer: Option<MutableBuffer>, finallymutbuffer: Option<MutableBuffer>) -> Self {
+        Try { mutbuffer, identifier, catchmutbuffer, finallymutbuffer }
+    }
+}
+
+fn is_catchable(error: &CreateError) -> bool {
+    error.exit_status() != EXIT_SYNTAX && !matches!(error.code, 11 | 12 | 19 | 23)
+}
+
+fn describe_error(error: &CreateError, runtime: &mut Runtime) -> CreateAny {
+    let kind = match (error.code, error.exit_status()) {
+        (25, _) => "user",
+        (_, EXIT_IO) => "io",
+        _ => "runtime",
+    };
+    let location = match runtime.import_stack.last() {
+        Some(p) => p.display().to_string(),
+        None => String::new(),
+    };
+    let mut scope = PrimitiveScope::new();
+    scope.insert("kind".to_string(), write_text(kind));
+    scope.insert("code".to_string(), CreateAny::BUF(error.code as Buffer));
+    scope.insert("message".to_string(), write_text(&error.message));
+    scope.insert("location".to_string(), write_text(&location));
+    scope.insert("value".to_string(), if error.code == 25 {runtime.thrown.take().unwrap_or(CreateAny::NUL())} else {CreateAny::NUL()});
+    CreateAny::SCP(scope)
+}
+
+pub struct And {
+    left: MutableBuffer,
+    right: MutableBuffer,
+}
+
+impl Controller for And {
+    fn run(&mut self, environment: &mut Environment, lossy: bool) -> CreateResult {
+        let result = match read_condition(&self.left, environment, lossy) {
+            Ok(true) => match read_condition(&self.right, environment, lossy) {
+                Ok(v) => v,
+                Err(e) => return CreateResult::Err(e),
+            },
+            Ok(false) => false,
+            Err(e) => return CreateResult::Err(e),
+        };
+        write(environment, CreateAny::BUF(if result {1.} else {0.}), lossy)
+    }
+
+    fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
+        Rc::new(RefCell::new(And::new(self.left.clone(), self.right.clone())))
+    }
+
+    fn return_count(&self) -> usize {1}
+}
+
+impl And {
+    pub fn new(left: MutableBuffer, right: MutableBuffer) -> Self {
+        And { left, right }
+    }
+}
+
+pub struct Or {
+    left: MutableBuffer,
+    right: MutableBuffer,
+}
+
+impl Controller for Or {
+    fn run(&mut self, environment: &mut Environment, lossy: bool) -> CreateResult {
+        let result = match read_condition(&self.left, environment, lossy) {
+            Ok(true) => true,
+            Ok(false) => match read_condition(&self.right, environment, lossy) {
+                Ok(v) => v,
+                Err(e) => return CreateResult::Err(e),
+            },
+            Err(e) => return CreateResult::Err(e),
+        };
+        write(environment, CreateAny::BUF(if result {1.} else {0.}), lossy)
+    }
+
+    fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
+        Rc::new(RefCell::new(Or::new(self.left.clone(), self.right.clone())))
+    }
+
+    fn return_count(&self) -> usize {1}
+}
+
+impl Or {
+    pub fn new(left: MutableBuffer, right: MutableBuffer) -> Self {
+        Or { left, right }
+    }
+}
+
+fn read_condition(mutbuffer: &MutableBuffer, environment: &mut Environment, lossy: bool) -> Result<bool, CreateError> {
+    match mutbuffer.eval_clone_return(environment, lossy)? {
+        Some(v) => match *v {
+            CreateAny::BUF(b) => Ok(is_truthy(b)),
+            _ => Err(CreateError { code: 9, message: "Logical operator operand did not return a buffer".to_string() }),
+        },
+        None => Err(CreateError { code: 9, message: "Logical operator operands cannot be null".to_string() }),
     }
 }
 
@@ -204,7 +465,7 @@ pub struct Scoped {
 impl Controller for Scoped {
     fn run(&mut self, environment: &mut Environment, lossy: bool) -> CreateResult {
         let mut scope = Scope::new(environment.scope);
-        let mut environment = Environment { buffers: environment.buffers, writers: &mut Writers::new(), scope: &mut scope };
+        let mut environment = Environment { buffers: environment.buffers, writers: &mut Writers::new(), scope: &mut scope, runtime: environment.runtime };
         while let Some(mut mutbuffer) = self.mutbuffers.pop() {
             match mutbuffer.evaluate(&mut environment, lossy) {
                 CreateResult::Ok() => (),
@@ -225,3 +486,34 @@ impl Scoped {
         Scoped { mutbuffers }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::tokenizer::tokenize;
+
+    fn run(source: &str) -> PrimitiveScope {
+        let mut scope = PrimitiveScope::new();
+        let result = interpret_in_scope(tokenize(source).unwrap(), &mut scope, &mut Runtime::new());
+        assert!(matches!(result, CreateResult::Ok()));
+        scope
+    }
+
+    #[test]
+    fn finally_keeps_the_pending_return_value() {
+        let scope = run("=()g ( ) buf { return 5 } =()f ( ) buf { try { return 1 } finally { =x ~g( ) } } =r ~f( )");
+        assert!(scope.get("r") == Some(&CreateAny::BUF(1.)));
+    }
+
+    #[test]
+    fn finally_keeps_the_pending_loop_label() {
+        let scope = run("=n 0 @outer for 3 { for 3 { try { break @outer } finally { @inner for 2 { break @inner } } } =n + ~n 1 }");
+        assert!(scope.get("n") == Some(&CreateAny::BUF(0.)));
+    }
+
+    #[test]
+    fn finally_keeps_the_pending_thrown_value() {
+        let scope = run("=v 0 try { try { throw 4 } finally { try { throw 8 } catch { } } } catch =e { =v ~e.value }");
+        assert!(scope.get("v") == Some(&CreateAny::BUF(4.)));
+    }
+}
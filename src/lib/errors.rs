@@ -21,6 +21,18 @@ impl std::fmt::Display for CreateError {
             11 => "Unexpected break statement.",
             12 => "Unexpected return statement.",
             13 => "Scope did not return value.",
+            14 => "Integer operation overflowed or divided by zero.",
+            15 => "Arithmetic produced an invalid number.",
+            16 => "There was an invalid format template.",
+            17 => "Tried to print an invalid char.",
+            18 => "Could not read from input.",
+            19 => "The program exited.",
+            20 => "File access was not permitted.",
+            21 => "Could not access file.",
+            22 => "There was a circular import.",
+            23 => "Unexpected continue statement.",
+            24 => "Could not destructure value.",
+            25 => "A user error was thrown.",
             usize::MAX => "Something went wrong.",
             _ => "Huh, we weren't able to diagnose the issue, but there was an error somewhere in here.",
         };
@@ -28,6 +40,20 @@ impl std::fmt::Display for CreateError {
     }
 }
 
+pub const EXIT_RUNTIME: u8 = 1;
+pub const EXIT_SYNTAX: u8 = 2;
+pub const EXIT_IO: u8 = 3;
+
+impl CreateError {
+    pub fn exit_status(&self) -> u8 {
+        match self.code {
+            2 => EXIT_SYNTAX,
+            1 | 18 | 20 | 21 => EXIT_IO,
+            _ => EXIT_RUNTIME,
+        }
+    }
+}
+
 impl std::fmt::Debug for CreateError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "An error was thrown. Details follow:\nError Code: {}\nError Message: {}\n", self.code, self.message)
@@ -0,0 +1,151 @@
+use super::interpreter::*;
+use super::errors::*;
+use super::output::*;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+pub fn allow_path(dir: &str) -> Result<PathBuf, CreateError> {
+    Path::new(dir).canonicalize().map_err(|e| file_error(dir, e))
+}
+
+fn file_error(path: &str, err: std::io::Error) -> CreateError {
+    CreateError { code: 21, message: format!("{}: {}", path, err) }
+}
+
+pub fn resolve_path(path: &str) -> Option<PathBuf> {
+    let path = std::env::current_dir().ok()?.join(path);
+    let components = path.components().collect::<Vec<Component>>();
+    for existing in (1..=components.len()).rev() {
+        if let Ok(mut resolved) = components[..existing].iter().collect::<PathBuf>().canonicalize() {
+            for component in &components[existing..] {
+                match component {
+                    Component::ParentDir => {resolved.pop();},
+                    Component::Normal(name) => {
+                        resolved.push(name);
+                        // anything already here could not be canonicalized, so it is a dangling symlink
+                        if resolved.symlink_metadata().is_ok() {return None}
+                    },
+                    _ => (),
+                }
+            }
+            return Some(resolved);
+        }
+    }
+    None
+}
+
+fn permit(name: &str, path: &str, allowed: &[PathBuf], access: &str) -> Result<PathBuf, CreateError> {
+    match resolve_path(path) {
+        Some(p) if allowed.iter().any(|dir| p.starts_with(dir)) => Ok(p),
+        _ => Err(CreateError { code: 20, message: format!("{} was not permitted to {} {} (see --allow-{})", name, access, path, access) }),
+    }
+}
+
+fn read_path(name: &str, values: &mut std::vec::IntoIter<CreateAny>, environment: &Environment) -> Result<(String, PathBuf), CreateError> {
+    let path = read_text(name, &values.next().unwrap())?;
+    let resolved = permit(name, &path, &environment.runtime.allow_read, "read")?;
+    Ok((path, resolved))
+}
+
+fn write_path(name: &str, values: &mut std::vec::IntoIter<CreateAny>, environment: &Environment) -> Result<(String, PathBuf), CreateError> {
+    let path = read_text(name, &values.next().unwrap())?;
+    let resolved = permit(name, &path, &environment.runtime.allow_write, "write")?;
+    Ok((path, resolved))
+}
+
+pub fn read_file(values: Vec<CreateAny>, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let (path, resolved) = read_path("readfile", &mut values.into_iter(), environment)?;
+    let text = std::fs::read_to_string(resolved).map_err(|e| file_error(&path, e))?;
+    Ok(write_text(&text))
+}
+
+pub fn read_file_lines(values: Vec<CreateAny>, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let (path, resolved) = read_path("readlines", &mut values.into_iter(), environment)?;
+    let text = std::fs::read_to_string(resolved).map_err(|e| file_error(&path, e))?;
+    Ok(CreateAny::ARR(text.lines().map(write_text).collect()))
+}
+
+pub fn write_file(values: Vec<CreateAny>, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let (path, resolved) = write_path("writefile", &mut values, environment)?;
+    let value = values.next().unwrap();
+    std::fs::write(resolved, read_text("writefile", &value)?).map_err(|e| file_error(&path, e))?;
+    Ok(value)
+}
+
+pub fn append_file(values: Vec<CreateAny>, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let mut values = values.into_iter();
+    let (path, resolved) = write_path("appendfile", &mut values, environment)?;
+    let value = values.next().unwrap();
+    let text = read_text("appendfile", &value)?;
+    std::fs::OpenOptions::new().append(true).create(true).open(resolved)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| file_error(&path, e))?;
+    Ok(value)
+}
+
+pub fn file_exists(values: Vec<CreateAny>, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let (_, resolved) = read_path("exists", &mut values.into_iter(), environment)?;
+    Ok(CreateAny::BUF(if resolved.exists() {1.} else {0.}))
+}
+
+pub fn list_dir(values: Vec<CreateAny>, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let (path, resolved) = read_path("listdir", &mut values.into_iter(), environment)?;
+    let mut names = std::fs::read_dir(resolved)
+        .and_then(|entries| entries.map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned())).collect::<Result<Vec<String>, std::io::Error>>())
+        .map_err(|e| file_error(&path, e))?;
+    names.sort();
+    Ok(CreateAny::ARR(names.iter().map(|n| write_text(n)).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("create-files-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("box")).unwrap();
+        root.canonicalize().unwrap()
+    }
+
+    fn permitted(path: &Path, allowed: &Path) -> bool {
+        permit("test", path.to_str().unwrap(), &[allowed.to_path_buf()], "write").is_ok()
+    }
+
+    #[test]
+    fn resolves_missing_files_inside_existing_directories() {
+        let root = sandbox("missing");
+        assert_eq!(resolve_path(root.join("box/zz/new.txt").to_str().unwrap()), Some(root.join("box/zz/new.txt")));
+        assert!(permitted(&root.join("box/zz/new.txt"), &root.join("box")));
+    }
+
+    #[test]
+    fn resolves_parent_components() {
+        let root = sandbox("parent");
+        assert_eq!(resolve_path(root.join("box/zz/../new.txt").to_str().unwrap()), Some(root.join("box/new.txt")));
+        assert!(permitted(&root.join("box/zz/../new.txt"), &root.join("box")));
+        assert!(!permitted(&root.join("box/../escaped.txt"), &root.join("box")));
+        assert!(!permitted(&root.join("box/zz/../../escaped.txt"), &root.join("box")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_symlinked_directories() {
+        let root = sandbox("linkdir");
+        std::fs::create_dir(root.join("outside")).unwrap();
+        std::os::unix::fs::symlink(root.join("outside"), root.join("box/link")).unwrap();
+        assert_eq!(resolve_path(root.join("box/link/new.txt").to_str().unwrap()), Some(root.join("outside/new.txt")));
+        assert!(!permitted(&root.join("box/link/new.txt"), &root.join("box")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_dangling_symlinks() {
+        let root = sandbox("dangling");
+        std::os::unix::fs::symlink(root.join("escaped"), root.join("box/link")).unwrap();
+        assert_eq!(resolve_path(root.join("box/link").to_str().unwrap()), None);
+        assert!(!permitted(&root.join("box/link"), &root.join("box")));
+        assert!(!permitted(&root.join("box/link/new.txt"), &root.join("box")));
+    }
+}
@@ -7,34 +7,69 @@ use super::errors::*;
 pub struct Function {
     params: Box<Vec<(CreateType, String)>>,
     mutbuffer: Box<MutableBuffer>,
-    returntype: Box<CreateType>,
+    returntypes: Box<Vec<CreateType>>,
 }
 
 impl Function {
-    pub fn new(params: Vec<(CreateType, String)>, mutbuffer: MutableBuffer, returntype: CreateType) -> Self {
-        Function { params: Box::new(params), mutbuffer: Box::new(mutbuffer), returntype: Box::new(returntype)}
+    pub fn new(params: Vec<(CreateType, String)>, mutbuffer: MutableBuffer, returntypes: Vec<CreateType>) -> Self {
+        Function { params: Box::new(params), mutbuffer: Box::new(mutbuffer), returntypes: Box::new(returntypes)}
     }
 
     pub fn evaluate(&self, params: &mut Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+        let mut values = self.evaluate_all(params, environment, lossy)?;
+        if values.len() != 1 {
+            return Err(CreateError { code: 10, message: format!("Function returns {} values where one was expected", values.len()) });
+        }
+        Ok(values.pop().unwrap())
+    }
+
+    pub fn evaluate_all(&self, params: &mut Vec<CreateAny>, environment: &mut Environment, lossy: bool) -> Result<Vec<CreateAny>, CreateError> {
+        if params.len() != self.params.len() {
+            return Err(CreateError { code: 10, message: format!("Function expected {} arguments but was given {}", self.params.len(), params.len()) });
+        }
         let mut priv_scope = Scope::new(environment.scope);
         for (index, (param, val)) in self.params.iter().zip(params).enumerate() {
             if !param.0.matches(val) {return Err(CreateError { code: 10, message: format!("Argument {} in function call was mistyped (type {:?}), should be {:?}", index, val.get_type(), param.0) })}
-            priv_scope.insert(param.1.clone(), val.clone());
+            priv_scope.insert_locally(param.1.clone(), val.clone());
         }
         let mut partitioned_buffers = PartitionedBuffers::new(environment.buffers);
-        match self.mutbuffer.clone().evaluate_clone(&mut Environment { buffers: &mut partitioned_buffers, writers: &mut Writers::new(), scope: &mut priv_scope }, lossy) {
-            CreateResult::Ok() => (),
+        let values = match self.mutbuffer.clone().evaluate_clone(&mut Environment { buffers: &mut partitioned_buffers, writers: &mut Writers::new(), scope: &mut priv_scope, runtime: environment.runtime }, lossy) {
+            CreateResult::Ok() if self.returntypes.len() == 1 => vec![*partitioned_buffers.get_return().unwrap_or(Box::new(CreateAny::NUL()))],
+            CreateResult::Ok() => {
+                let mut values = partitioned_buffers.get_returns();
+                values.split_off(values.len().saturating_sub(self.returntypes.len()))
+            },
             CreateResult::Err(e) => match e.code {
-                12 => (),
+                12 => environment.runtime.return_value.take().unwrap_or(vec![CreateAny::NUL()]),
                 _ => return Err(e),
             }
-        }
-        let r = partitioned_buffers.get_return().unwrap_or(Box::new(CreateAny::NUL()));
-        if self.returntype.matches(&*r) {
-            Ok(*r)
+        };
+        let found = values.iter().map(|v| v.get_type()).collect::<Vec<CreateType>>();
+        if values.len() == self.returntypes.len() && self.returntypes.iter().zip(&values).all(|(t, v)| t.matches(v)) {
+            Ok(values)
         } else {
-            Err(CreateError { code: 3, message: format!("Improper function return (expected {:?})", self.returntype) })
+            Err(CreateError { code: 3, message: format!("Improper function return (expected {:?}, found {:?})", self.returntypes, found) })
+        }
+    }
+}
+
+impl std::fmt::Display for Function {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "(")?;
+        for (t, n) in self.params.iter() {
+            let prefix = match t {
+                CreateType::ARR => "=[]",
+                CreateType::FUN => "=()",
+                CreateType::SCP => "=||",
+                _ => "=",
+            };
+            write!(fmt, " {}{}", prefix, n)?;
+        }
+        write!(fmt, " )")?;
+        for t in self.returntypes.iter() {
+            write!(fmt, " {}", t)?;
         }
+        Ok(())
     }
 }
 
@@ -56,6 +91,14 @@ impl FunctionCall {
             Err(e) => return Err(e),
         }
     }
+
+    fn get_container(&self, environment: &Environment) -> Option<PrimitiveScope> {
+        if self.name.len() < 2 {return None}
+        match resolve_identifier_immut(&self.name[1..].to_vec(), environment.scope) {
+            Ok(CreateAny::SCP(s)) => Some(s.clone()),
+            _ => None,
+        }
+    }
 }
 
 impl Controller for FunctionCall {
@@ -73,9 +116,21 @@ impl Controller for FunctionCall {
             });
         }
         params.reverse();
-        match function.evaluate(&mut params, environment, lossy) {
-            Ok(v) => {write(environment, v, lossy);},
+        let result = match self.get_container(environment) {
+            Some(members) => function.evaluate_all(&mut params, &mut Environment { buffers: environment.buffers, writers: environment.writers, scope: &mut Scope::with(environment.scope, members), runtime: environment.runtime }, lossy),
+            None => function.evaluate_all(&mut params, environment, lossy),
+        };
+        let values = match result {
+            Ok(v) => v,
             Err(e) => return CreateResult::Err(e),
+        };
+        if values.len() > 1 && !environment.writers.is_empty() {
+            return CreateResult::Err(CreateError { code: 3, message: format!("Function {:?} returned {} values where one was expected", self.name, values.len()) });
+        }
+        for v in values {
+            if let CreateResult::Err(e) = write(environment, v, lossy) {
+                return CreateResult::Err(e);
+            }
         }
         CreateResult::Ok()
     }
@@ -86,3 +141,54 @@ impl Controller for FunctionCall {
 
     fn return_count(&self) -> usize {1}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::runtime::Runtime;
+    use crate::lib::tokenizer::tokenize;
+
+    const DIVMOD: &str = "=()divmod ( =a =b ) buf buf { return idiv ~a ~b emod ~a ~b } ";
+
+    fn run(source: &str) -> Result<PrimitiveScope, CreateError> {
+        let mut scope = PrimitiveScope::new();
+        match interpret_in_scope(tokenize(&format!("{}{}", DIVMOD, source))?, &mut scope, &mut Runtime::new()) {
+            CreateResult::Ok() => Ok(scope),
+            CreateResult::Err(e) => Err(e),
+        }
+    }
+
+    fn value(scope: &PrimitiveScope, name: &str) -> Option<Buffer> {
+        match scope.get(name) {
+            Some(CreateAny::BUF(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn destructures_multiple_return_values() {
+        let scope = run("=q =r ~divmod( 7 2 )").ok().unwrap();
+        assert_eq!((value(&scope, "q"), value(&scope, "r")), (Some(3.), Some(1.)));
+    }
+
+    #[test]
+    fn destructures_calls_through_function_parameters() {
+        let scope = run("=()first ( =()f ) buf { =a =b ~f( 7 3 ) ~a } =x ~first( ~divmod )").ok().unwrap();
+        assert_eq!(value(&scope, "x"), Some(2.));
+    }
+
+    #[test]
+    fn destructures_calls_to_scope_fields_and_later_definitions() {
+        let scope = run("=||s | =()two ( ) buf buf { return 1 2 } | =x =y ~s.two( ) =()early ( ) buf buf { return ~late( ) } =()late ( ) buf buf { return 10 20 } =c =d ~early( )").ok().unwrap();
+        assert_eq!((value(&scope, "x"), value(&scope, "y")), (Some(1.), Some(2.)));
+        assert_eq!((value(&scope, "c"), value(&scope, "d")), (Some(10.), Some(20.)));
+    }
+
+    #[test]
+    fn rejects_multiple_values_where_one_is_expected() {
+        for source in ["=x ~divmod( 7 2 )", "=[]a [ ~divmod( 7 2 ) ]", "=x + 1 ~divmod( 7 2 )", "println ~divmod( 7 2 )", "=()f ( =a ) buf { ~a } =x ~f( ~divmod( 7 2 ) )"] {
+            assert_eq!(run(source).err().map(|e| e.code), Some(3), "{}", source);
+        }
+        assert_eq!(run("=x =y =z ~divmod( 7 2 )").err().map(|e| e.code), Some(24));
+    }
+}
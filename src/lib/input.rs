@@ -0,0 +1,177 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::io::{BufRead, Write};
+use super::interpreter::*;
+use super::errors::*;
+use super::output::*;
+use super::utils::*;
+
+#[derive(Clone, Debug)]
+pub enum InputKind {
+    Number,
+    Char,
+    Line,
+    All,
+    Eof,
+}
+
+#[derive(Clone)]
+pub struct Input {
+    kind: InputKind,
+}
+
+impl Input {
+    pub fn new(kind: InputKind) -> Self {
+        Input { kind }
+    }
+}
+
+impl Controller for Input {
+    fn run(&mut self, environment: &mut Environment, lossy: bool) -> CreateResult {
+        if let Err(e) = std::io::stdout().flush() {
+            return CreateResult::Err(e.into());
+        }
+        let input = &mut *environment.runtime.input;
+        let val = match self.kind {
+            InputKind::Number => read_input_number(input).map(|v| v.map(CreateAny::BUF)),
+            InputKind::Char => read_input_char(input).map(|v| v.map(|c| CreateAny::BUF(c as u32 as Buffer))),
+            InputKind::Line => read_input_line(input).map(|v| v.map(|l| write_text(&l))),
+            InputKind::All => read_input_all(input).map(|v| v.map(|t| write_text(&t))),
+            InputKind::Eof => at_eof(input).map(|v| Some(CreateAny::BUF(if v {1.} else {0.}))),
+        };
+        match val {
+            Ok(Some(v)) => write(environment, v, lossy),
+            Ok(None) => write(environment, CreateAny::NUL(), lossy),
+            Err(e) => CreateResult::Err(e),
+        }
+    }
+
+    fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
+        Rc::new(RefCell::new(self.clone()))
+    }
+
+    fn return_count(&self) -> usize {1}
+}
+
+fn at_eof(input: &mut dyn BufRead) -> Result<bool, CreateError> {
+    Ok(input.fill_buf()?.is_empty())
+}
+
+pub fn read_input_char(input: &mut dyn BufRead) -> Result<Option<char>, CreateError> {
+    let first = match input.fill_buf()?.first() {
+        Some(b) => *b,
+        None => return Ok(None),
+    };
+    let width = match first {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return Err(CreateError { code: 18, message: format!("Input contained the invalid UTF-8 byte {:#x}", first) }),
+    };
+    let mut bytes = vec![0; width];
+    if let Err(e) = input.read_exact(&mut bytes) {
+        return Err(CreateError { code: 18, message: format!("Input ended in the middle of a UTF-8 char: {}", e) });
+    }
+    match std::str::from_utf8(&bytes) {
+        Ok(s) => Ok(s.chars().next()),
+        Err(_) => Err(CreateError { code: 18, message: "Input contained an invalid UTF-8 sequence".to_string() }),
+    }
+}
+
+pub fn read_input_line(input: &mut dyn BufRead) -> Result<Option<String>, CreateError> {
+    let mut line = String::new();
+    if input.read_line(&mut line).map_err(read_error)? == 0 {
+        return Ok(None);
+    }
+    if line.ends_with('\n') {line.pop();}
+    if line.ends_with('\r') {line.pop();}
+    Ok(Some(line))
+}
+
+pub fn read_input_all(input: &mut dyn BufRead) -> Result<Option<String>, CreateError> {
+    let mut text = String::new();
+    if input.read_to_string(&mut text).map_err(read_error)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(text))
+}
+
+pub fn read_input_number(input: &mut dyn BufRead) -> Result<Option<Buffer>, CreateError> {
+    while let Some(b) = input.fill_buf()?.first() {
+        if !b.is_ascii_whitespace() {break}
+        input.consume(1);
+    }
+    let mut word = String::new();
+    while let Some(b) = input.fill_buf()?.first() {
+        if b.is_ascii_whitespace() {break}
+        match read_input_char(input)? {
+            Some(c) => word.push(c),
+            None => break,
+        }
+    }
+    if word.is_empty() {
+        return Ok(None);
+    }
+    match read_number(&word) {
+        Some(v) => Ok(Some(v)),
+        None => Err(CreateError { code: 18, message: format!("Could not read {} from input as a number", word) }),
+    }
+}
+
+fn read_error(err: std::io::Error) -> CreateError {
+    match err.kind() {
+        std::io::ErrorKind::InvalidData => CreateError { code: 18, message: "Input contained an invalid UTF-8 sequence".to_string() },
+        _ => err.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn reads_numbers_separated_by_whitespace() {
+        let mut input = Cursor::new("  12 -3.5\n0x10\t");
+        assert_eq!(read_input_number(&mut input).ok(), Some(Some(12.)));
+        assert_eq!(read_input_number(&mut input).ok(), Some(Some(-3.5)));
+        assert_eq!(read_input_number(&mut input).ok(), Some(Some(16.)));
+        assert_eq!(read_input_number(&mut input).ok(), Some(None));
+    }
+
+    #[test]
+    fn rejects_words_that_are_not_numbers() {
+        let mut input = Cursor::new("twelve");
+        assert_eq!(read_input_number(&mut input).err().map(|e| e.code), Some(18));
+    }
+
+    #[test]
+    fn reads_numbers_at_eof() {
+        assert_eq!(read_input_number(&mut Cursor::new("")).ok(), Some(None));
+        assert_eq!(read_input_number(&mut Cursor::new(" \n ")).ok(), Some(None));
+        assert_eq!(read_input_number(&mut Cursor::new("7")).ok(), Some(Some(7.)));
+    }
+
+    #[test]
+    fn reads_multibyte_chars() {
+        let mut input = Cursor::new("aé€😀");
+        for expected in ['a', 'é', '€', '😀'] {
+            assert_eq!(read_input_char(&mut input).ok(), Some(Some(expected)));
+        }
+        assert_eq!(read_input_char(&mut input).ok(), Some(None));
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        assert_eq!(read_input_char(&mut Cursor::new(vec![0xFF])).err().map(|e| e.code), Some(18));
+        assert_eq!(read_input_char(&mut Cursor::new(vec![0x80, b'a'])).err().map(|e| e.code), Some(18));
+        assert_eq!(read_input_char(&mut Cursor::new(vec![0xC3, b'a'])).err().map(|e| e.code), Some(18));
+        assert_eq!(read_input_number(&mut Cursor::new(vec![b'1', 0xFF])).err().map(|e| e.code), Some(18));
+    }
+
+    #[test]
+    fn rejects_chars_cut_off_by_eof() {
+        assert_eq!(read_input_char(&mut Cursor::new(vec![0xE2, 0x82])).err().map(|e| e.code), Some(18));
+    }
+}
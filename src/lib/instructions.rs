@@ -4,16 +4,20 @@ use crate::lib::interpreter::*;
 use crate::lib::errors::*;
 
 pub struct BinaryOp {
+    name: &'static str,
     left: Option<Buffer>,
     right: Option<Buffer>,
     op: fn(Buffer, Buffer) -> Buffer,
 }
 
 impl Instruction for BinaryOp {
-    fn evaluate(&mut self, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    fn evaluate(&mut self, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
         if let Some(l) = self.left {
             if let Some(r) = self.right {
-                return Ok(CreateAny::BUF((self.op)(l,r)));
+                if environment.runtime.strict_math && matches!(self.name, "/" | "%") && r == 0. {
+                    return Err(CreateError { code: 15, message: format!("Operator {} divided {} by zero", self.name, l) });
+                }
+                return Ok(CreateAny::BUF(check_result(self.name, &[l, r], (self.op)(l,r), environment)?));
             }
         }
         Err(CreateError { code: 5, message: "There was an unfilled value within a Binary Operator".to_string() })
@@ -52,25 +56,26 @@ impl Instruction for BinaryOp {
     fn capacity(&self) -> Result<usize, CreateError> {Ok(2)}
 
     fn clone_ins(&self) -> Rc<RefCell<dyn Instruction>> {
-        Rc::new(RefCell::new(BinaryOp { left: self.left.clone(), right: self.right.clone(), op: self.op.clone() }))
+        Rc::new(RefCell::new(BinaryOp { name: self.name, left: self.left, right: self.right, op: self.op }))
     }
 }
 
 impl BinaryOp {
-    pub fn new(op: fn(Buffer, Buffer) -> Buffer) -> Self {
-        BinaryOp { left: None, right: None, op }
+    pub fn new(name: &'static str, op: fn(Buffer, Buffer) -> Buffer) -> Self {
+        BinaryOp { name, left: None, right: None, op }
     }
 }
 
 pub struct UnaryOp {
+    name: &'static str,
     value: Option<Buffer>,
     op: fn(Buffer) -> Buffer,
 }
 
 impl Instruction for UnaryOp {
-    fn evaluate(&mut self, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    fn evaluate(&mut self, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
         if let Some(v) = self.value {
-            Ok(CreateAny::BUF((self.op)(v)))
+            Ok(CreateAny::BUF(check_result(self.name, &[v], (self.op)(v), environment)?))
         } else {
             Err(CreateError { code: 5, message: "There was an unfilled value in a Unary Operator".to_string() })
         }
@@ -100,12 +105,216 @@ impl Instruction for UnaryOp {
     fn capacity(&self) -> Result<usize, CreateError> {Ok(1)}
 
     fn clone_ins(&self) -> Rc<RefCell<dyn Instruction>> {
-        Rc::new(RefCell::new(UnaryOp { value: self.value.clone(), op: self.op.clone() }))
+        Rc::new(RefCell::new(UnaryOp { name: self.name, value: self.value, op: self.op }))
     }
 }
 
 impl UnaryOp {
-    pub fn new(op: fn(Buffer) -> Buffer) -> Self {
-        UnaryOp { value: None, op }
+    pub fn new(name: &'static str, op: fn(Buffer) -> Buffer) -> Self {
+        UnaryOp { name, value: None, op }
+    }
+}
+
+pub struct TernaryOp {
+    name: &'static str,
+    first: Option<Buffer>,
+    second: Option<Buffer>,
+    third: Option<Buffer>,
+    op: fn(Buffer, Buffer, Buffer) -> Buffer,
+}
+
+impl Instruction for TernaryOp {
+    fn evaluate(&mut self, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+        if let (Some(a), Some(b), Some(c)) = (self.first, self.second, self.third) {
+            return Ok(CreateAny::BUF(check_result(self.name, &[a, b, c], (self.op)(a,b,c), environment)?));
+        }
+        Err(CreateError { code: 5, message: "There was an unfilled value within a Ternary Operator".to_string() })
+    }
+
+    fn write_buffer(&mut self, value: CreateAny) -> CreateResult {
+        let b = match value {
+            CreateAny::BUF(b) => b,
+            _ => return CreateResult::Err(CreateError { code: 3, message: "Tried to add a non-buffer to a Ternary Operator".to_string() }),
+        };
+        if self.first.is_none() {
+            self.first = Some(b);
+        } else if self.second.is_none() {
+            self.second = Some(b);
+        } else if self.third.is_none() {
+            self.third = Some(b);
+        } else {
+            return CreateResult::Err(CreateError { code: 3, message: "Tried to add a value to a filled Ternary Operator".to_string() });
+        }
+        CreateResult::Ok()
+    }
+
+    fn is_full(&self) -> Result<bool, CreateError> {
+        Ok(self.third.is_some())
+    }
+
+    fn capacity(&self) -> Result<usize, CreateError> {Ok(3)}
+
+    fn clone_ins(&self) -> Rc<RefCell<dyn Instruction>> {
+        Rc::new(RefCell::new(TernaryOp { name: self.name, first: self.first, second: self.second, third: self.third, op: self.op }))
+    }
+}
+
+impl TernaryOp {
+    pub fn new(name: &'static str, op: fn(Buffer, Buffer, Buffer) -> Buffer) -> Self {
+        TernaryOp { name, first: None, second: None, third: None, op }
+    }
+}
+
+pub struct IntegerBinaryOp {
+    name: &'static str,
+    left: Option<Buffer>,
+    right: Option<Buffer>,
+    op: fn(i64, i64) -> Option<i64>,
+}
+
+impl Instruction for IntegerBinaryOp {
+    fn evaluate(&mut self, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+        if let (Some(l), Some(r)) = (self.left, self.right) {
+            return match (self.op)(to_integer(self.name, l)?, to_integer(self.name, r)?) {
+                Some(v) => Ok(CreateAny::BUF(v as Buffer)),
+                None => Err(CreateError { code: 14, message: format!("Operator {} on {} and {} overflowed or divided by zero", self.name, l, r) }),
+            };
+        }
+        Err(CreateError { code: 5, message: "There was an unfilled value within an Integer Binary Operator".to_string() })
+    }
+
+    fn write_buffer(&mut self, value: CreateAny) -> CreateResult {
+        let b = match value {
+            CreateAny::BUF(b) => b,
+            _ => return CreateResult::Err(CreateError { code: 3, message: "Tried to add a non-buffer to an Integer Binary Operator".to_string() }),
+        };
+        if self.left.is_none() {
+            self.left = Some(b);
+        } else if self.right.is_none() {
+            self.right = Some(b);
+        } else {
+            return CreateResult::Err(CreateError { code: 3, message: "Tried to add a value to a filled Integer Binary Operator".to_string() });
+        }
+        CreateResult::Ok()
+    }
+
+    fn is_full(&self) -> Result<bool, CreateError> {
+        Ok(self.right.is_some())
+    }
+
+    fn capacity(&self) -> Result<usize, CreateError> {Ok(2)}
+
+    fn clone_ins(&self) -> Rc<RefCell<dyn Instruction>> {
+        Rc::new(RefCell::new(IntegerBinaryOp { name: self.name, left: self.left, right: self.right, op: self.op }))
+    }
+}
+
+impl IntegerBinaryOp {
+    pub fn new(name: &'static str, op: fn(i64, i64) -> Option<i64>) -> Self {
+        IntegerBinaryOp { name, left: None, right: None, op }
+    }
+}
+
+pub struct IntegerUnaryOp {
+    name: &'static str,
+    value: Option<Buffer>,
+    op: fn(i64) -> Option<i64>,
+}
+
+impl Instruction for IntegerUnaryOp {
+    fn evaluate(&mut self, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+        if let Some(v) = self.value {
+            match (self.op)(to_integer(self.name, v)?) {
+                Some(r) => Ok(CreateAny::BUF(r as Buffer)),
+                None => Err(CreateError { code: 14, message: format!("Operator {} on {} overflowed", self.name, v) }),
+            }
+        } else {
+            Err(CreateError { code: 5, message: "There was an unfilled value in an Integer Unary Operator".to_string() })
+        }
+    }
+
+    fn write_buffer(&mut self, val: CreateAny) -> CreateResult {
+        if self.value.is_some() {
+            return CreateResult::Err(CreateError { code: 3, message: "Tried to add a value to a filled Integer Unary Operator".to_string() });
+        }
+        match val {
+            CreateAny::BUF(b) => {
+                self.value = Some(b);
+                CreateResult::Ok()
+            },
+            _ => CreateResult::Err(CreateError { code: 3, message: "Tried to add a non-buffer to an Integer Unary Operator".to_string() }),
+        }
+    }
+
+    fn is_full(&self) -> Result<bool, CreateError> {
+        Ok(self.value.is_some())
+    }
+
+    fn capacity(&self) -> Result<usize, CreateError> {Ok(1)}
+
+    fn clone_ins(&self) -> Rc<RefCell<dyn Instruction>> {
+        Rc::new(RefCell::new(IntegerUnaryOp { name: self.name, value: self.value, op: self.op }))
+    }
+}
+
+impl IntegerUnaryOp {
+    pub fn new(name: &'static str, op: fn(i64) -> Option<i64>) -> Self {
+        IntegerUnaryOp { name, value: None, op }
+    }
+}
+
+fn check_result(name: &str, operands: &[Buffer], result: Buffer, environment: &Environment) -> Result<Buffer, CreateError> {
+    if environment.runtime.strict_math && (result.is_nan() || (result.is_infinite() && operands.iter().all(|v| v.is_finite()))) {
+        let operands = operands.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", ");
+        return Err(CreateError { code: 15, message: format!("Operator {} produced {} from operands {}", name, result, operands) });
+    }
+    Ok(result)
+}
+
+fn to_integer(name: &str, value: Buffer) -> Result<i64, CreateError> {
+    if value.fract() == 0. && value >= i64::MIN as Buffer && value < i64::MAX as Buffer {
+        Ok(value as i64)
+    } else {
+        Err(CreateError { code: 7, message: format!("Operator {} requires integer operands, found {}", name, value) })
+    }
+}
+
+pub struct ValueOp {
+    name: &'static str,
+    values: Vec<CreateAny>,
+    arity: usize,
+    op: fn(Vec<CreateAny>, &mut Environment, bool) -> Result<CreateAny, CreateError>,
+}
+
+impl Instruction for ValueOp {
+    fn evaluate(&mut self, environment: &mut Environment, lossy: bool) -> Result<CreateAny, CreateError> {
+        if self.values.len() < self.arity {
+            return Err(CreateError { code: 5, message: format!("There was an unfilled value in operator {}", self.name) });
+        }
+        (self.op)(self.values.clone(), environment, lossy)
+    }
+
+    fn write_buffer(&mut self, value: CreateAny) -> CreateResult {
+        if self.values.len() >= self.arity {
+            return CreateResult::Err(CreateError { code: 3, message: format!("Tried to add a value to a filled operator {}", self.name) });
+        }
+        self.values.push(value);
+        CreateResult::Ok()
+    }
+
+    fn is_full(&self) -> Result<bool, CreateError> {
+        Ok(self.values.len() >= self.arity)
+    }
+
+    fn capacity(&self) -> Result<usize, CreateError> {Ok(self.arity)}
+
+    fn clone_ins(&self) -> Rc<RefCell<dyn Instruction>> {
+        Rc::new(RefCell::new(ValueOp { name: self.name, values: self.values.clone(), arity: self.arity, op: self.op }))
+    }
+}
+
+impl ValueOp {
+    pub fn new(name: &'static str, arity: usize, op: fn(Vec<CreateAny>, &mut Environment, bool) -> Result<CreateAny, CreateError>) -> Self {
+        ValueOp { name, values: Vec::new(), arity, op }
     }
 }
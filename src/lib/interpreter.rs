@@ -8,7 +8,27 @@ use super::errors::*;
 use super::instructions::*;
 use super::controllers::*;
 use super::functions::*;
+use super::arrays::*;
+use super::output::*;
+use super::input::*;
+use super::system::*;
+use super::files::*;
+use super::modules::*;
+use super::prelude::*;
 use super::utils::*;
+use super::runtime::*;
+
+/// Parser state visible to nested reads: the labels of enclosing loops and the return counts of enclosing function bodies.
+pub struct ParseContext {
+    labels: Vec<String>,
+    return_counts: Vec<usize>,
+}
+
+impl ParseContext {
+    pub fn new() -> Self {
+        ParseContext { labels: Vec::new(), return_counts: Vec::new() }
+    }
+}
 
 pub type Buffer = f32;
 pub type Array = Vec<CreateAny>;
@@ -38,6 +58,10 @@ impl Into<CreateAny> for PrimitiveScope {
     }
 }
 
+pub fn is_truthy(value: Buffer) -> bool {
+    value != 0. && !value.is_nan()
+}
+
 #[derive(Clone, Debug)]
 pub struct MutableBuffer(Vec<CreateDirective>);
 
@@ -73,7 +97,10 @@ impl MutableBuffer {
 
     pub fn eval_return(&mut self, environment: &mut Environment, lossy: bool) -> Result<Option<Box<CreateAny>>, CreateError> {
         let mut exposed_buffer = PartitionedBuffers::new(environment.buffers);
-        self.evaluate(&mut Environment { buffers: &mut exposed_buffer, writers: &mut Writers::new(), scope: environment.scope }, lossy);
+        if let CreateResult::Err(e) = self.evaluate(&mut Environment { buffers: &mut exposed_buffer, writers: &mut Writers::new(), scope: environment.scope, runtime: environment.runtime }, lossy) {
+            return Err(e);
+        }
+        check_single(exposed_buffer.new.len())?;
         match exposed_buffer.get_return() {
             Some(v) => Ok(Some(v)),
             None => Ok(None),
@@ -82,12 +109,39 @@ impl MutableBuffer {
 
     pub fn eval_clone_return(&self, environment: &mut Environment, lossy: bool) -> Result<Option<Box<CreateAny>>, CreateError> {
         let mut exposed_buffers = PartitionedBuffers::new(environment.buffers);
-        self.evaluate_clone(&mut Environment { buffers: &mut exposed_buffers, writers: &mut Writers::new(), scope: environment.scope }, lossy);
+        if let CreateResult::Err(e) = self.evaluate_clone(&mut Environment { buffers: &mut exposed_buffers, writers: &mut Writers::new(), scope: environment.scope, runtime: environment.runtime }, lossy) {
+            return Err(e);
+        }
+        check_single(exposed_buffers.new.len())?;
         match exposed_buffers.get_return() {
             Some(v) => Ok(Some(v)),
             None => Ok(None),
         }
     }
+
+    pub fn eval_clone_returns(&self, environment: &mut Environment, lossy: bool) -> Result<Vec<CreateAny>, CreateError> {
+        let mut exposed_buffers = PartitionedBuffers::new(environment.buffers);
+        if let CreateResult::Err(e) = self.evaluate_clone(&mut Environment { buffers: &mut exposed_buffers, writers: &mut Writers::new(), scope: environment.scope, runtime: environment.runtime }, lossy) {
+            return Err(e);
+        }
+        Ok(exposed_buffers.get_returns())
+    }
+
+    /// Evaluates an expression in place, such as the value of a setter, which must leave at most one new value on the buffer.
+    fn evaluate_single(&mut self, environment: &mut Environment, lossy: bool) -> CreateResult {
+        let before = environment.buffers.len();
+        if let CreateResult::Err(e) = self.evaluate(environment, lossy) {
+            return CreateResult::Err(e);
+        }
+        check_single(environment.buffers.len().saturating_sub(before)).into()
+    }
+}
+
+fn check_single(produced: usize) -> Result<(), CreateError> {
+    if produced > 1 {
+        return Err(CreateError { code: 3, message: format!("Expression produced {} values where one was expected", produced) });
+    }
+    Ok(())
 }
 
 impl<'a> std::ops::Deref for MutableBuffer {
@@ -118,13 +172,18 @@ pub enum CreateDirective {
     WRITE_NAR(Identifier),
     WRITE_NSC(Identifier),
     WRITE_NFN(Identifier),
+    WRITE_DST(Vec<(CreateType, Identifier)>),
     WRITE_GNB(Identifier),
     WRITE_GNA(Identifier),
     WRITE_LNB(Identifier),
     WRITE_LNA(Identifier),
+    WRITE_IAR(Identifier, Vec<MutableBuffer>, MutableBuffer),
+    WRITE_GIR(Identifier, Vec<MutableBuffer>, MutableBuffer),
+    WRITE_LIR(Identifier, Vec<MutableBuffer>, MutableBuffer),
     CONTROL(Rc<RefCell<dyn Controller>>),
-    BREAK(),
-    RETURN(),
+    BREAK(Option<String>),
+    CONTINUE(Option<String>),
+    RETURN(Vec<MutableBuffer>),
     REMOVE_BUF(),
 }
 
@@ -146,13 +205,18 @@ impl Clone for CreateDirective {
             WRITE_NAR(n) => WRITE_NAR(n.clone()),
             WRITE_NSC(n) => WRITE_NSC(n.clone()),
             WRITE_NFN(n) => WRITE_NFN(n.clone()),
+            WRITE_DST(t) => WRITE_DST(t.clone()),
             WRITE_GNB(n) => WRITE_GNB(n.clone()),
             WRITE_GNA(n) => WRITE_GNA(n.clone()),
             WRITE_LNB(n) => WRITE_LNB(n.clone()),
             WRITE_LNA(n) => WRITE_LNA(n.clone()),
+            WRITE_IAR(n, i, m) => WRITE_IAR(n.clone(), i.clone(), m.clone()),
+            WRITE_GIR(n, i, m) => WRITE_GIR(n.clone(), i.clone(), m.clone()),
+            WRITE_LIR(n, i, m) => WRITE_LIR(n.clone(), i.clone(), m.clone()),
             CONTROL(c) => CONTROL(c.borrow().clone_cfl()),
-            BREAK() => BREAK(),
-            RETURN() => RETURN(),
+            BREAK(l) => BREAK(l.clone()),
+            CONTINUE(l) => CONTINUE(l.clone()),
+            RETURN(m) => RETURN(m.clone()),
             REMOVE_BUF() => REMOVE_BUF(),
         }
     }
@@ -176,20 +240,25 @@ impl std::fmt::Debug for CreateDirective {
             WRITE_NAR(n) => format!("WRITE_NAR({:?})", n),
             WRITE_NSC(n) => format!("WRITE_NSC({:?})", n),
             WRITE_NFN(n) => format!("WRITE_NFN({:?})", n),
+            WRITE_DST(t) => format!("WRITE_DST({:?})", t),
             WRITE_LNB(n) => format!("WRITE_LNB({:?})", n),
             WRITE_LNA(n) => format!("WRITE_LNA({:?})", n),
             WRITE_GNB(n) => format!("WRITE_GNB({:?})", n),
             WRITE_GNA(n) => format!("WRITE_GNA({:?})", n),
+            WRITE_IAR(n, i, m) => format!("WRITE_IAR({:?}, {:?}, {:?})", n, i, m),
+            WRITE_GIR(n, i, m) => format!("WRITE_GIR({:?}, {:?}, {:?})", n, i, m),
+            WRITE_LIR(n, i, m) => format!("WRITE_LIR({:?}, {:?}, {:?})", n, i, m),
             CONTROL(_) => "CONTROL(...)".to_string(),
-            BREAK() => "BREAK".to_string(),
-            RETURN() => "RETURN".to_string(),
+            BREAK(l) => format!("BREAK({:?})", l),
+            CONTINUE(l) => format!("CONTINUE({:?})", l),
+            RETURN(m) => format!("RETURN({:?})", m),
             REMOVE_BUF() => "REMOVE_BUF".to_string(),
         };
         write!(f, "{}", string)
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CreateType {
     BUF,
     ARR,
@@ -211,6 +280,19 @@ impl CreateType {
     }
 }
 
+impl std::fmt::Display for CreateType {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use CreateType::*;
+        match self {
+            BUF => write!(fmt, "buf"),
+            ARR => write!(fmt, "arr"),
+            FUN => write!(fmt, "fun"),
+            SCP => write!(fmt, "scp"),
+            NUL => write!(fmt, "non"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum CreateAny {
     BUF(Buffer),
@@ -233,6 +315,48 @@ impl CreateAny {
     }
 }
 
+impl std::fmt::Display for CreateAny {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use CreateAny::*;
+        match self {
+            BUF(b) => write!(fmt, "{}", b),
+            ARR(a) => {
+                write!(fmt, "[")?;
+                for (i, v) in a.iter().enumerate() {
+                    if i > 0 {write!(fmt, " ")?}
+                    write!(fmt, "{}", v)?;
+                }
+                write!(fmt, "]")
+            },
+            FUN(f) => write!(fmt, "{}", f),
+            SCP(s) => {
+                let mut keys = s.keys().collect::<Vec<&String>>();
+                keys.sort();
+                write!(fmt, "|")?;
+                for (i, k) in keys.into_iter().enumerate() {
+                    if i > 0 {write!(fmt, " ")?}
+                    write!(fmt, "{}: {}", k, s[k])?;
+                }
+                write!(fmt, "|")
+            },
+            NUL() => write!(fmt, "non"),
+        }
+    }
+}
+
+impl PartialEq for CreateAny {
+    fn eq(&self, other: &Self) -> bool {
+        use CreateAny::*;
+        match (self, other) {
+            (BUF(a), BUF(b)) => a == b,
+            (ARR(a), ARR(b)) => a == b,
+            (SCP(a), SCP(b)) => a == b,
+            (NUL(), NUL()) => true,
+            _ => false,
+        }
+    }
+}
+
 impl std::fmt::Debug for CreateAny {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         use CreateAny::*;
@@ -272,6 +396,12 @@ pub trait Scoping {
     fn insert_locally(&mut self, key: String, value: CreateAny) -> Option<CreateAny> {
         self.insert(key, value)
     }
+    fn get_globally(&mut self, key: &String) -> Result<&mut CreateAny, CreateError> {
+        self.get(key)
+    }
+    fn get_locally(&mut self, key: &String) -> Result<&mut CreateAny, CreateError> {
+        self.get(key)
+    }
     fn contains_key(&self, key: &String) -> bool;
     fn scope_type(&self) -> &str {"unknown"}
     fn print_str(&self) -> &str {"scope"}
@@ -329,6 +459,10 @@ impl<'a> PartitionedBuffers<'a> {
         self.new.get(0)
     }
 
+    pub fn get_returns(self) -> Vec<CreateAny> {
+        (0..self.new.len()).rev().filter_map(|i| self.new.get(i)).map(|v| *v).collect()
+    }
+
     pub fn get_return_buf(self) -> Option<Box<Buffer>> {
         self.new.get_buf(0)
     }
@@ -410,23 +544,26 @@ impl Buffering for Buffers<'_> {
 
 impl<'a> Buffering for PartitionedBuffers<'a> {
     fn get(&self, index: usize) -> Option<Box<CreateAny>> {
-        match self.new.get(index) {
-            Some(v) => Some(v),
-            None => self.prev.get(index - self.new.len()),
+        if index < self.new.len() {
+            self.new.get(index)
+        } else {
+            self.prev.get(index - self.new.len())
         }
     }
 
     fn get_buf(&self, index: usize) -> Option<Box<Buffer>> {
-        match self.new.get_buf(index) {
-            Some(v) => Some(v),
-            None => self.prev.get_buf(index - self.new.len()),
+        if index < self.new.len() {
+            self.new.get_buf(index)
+        } else {
+            self.prev.get_buf(index - self.new.len())
         }
     }
 
     fn get_arr(&self, index: usize) -> Option<Box<Array>> {
-        match self.new.get_arr(index) {
-            Some(v) => Some(v),
-            None => self.prev.get_arr(index - self.new.len()),
+        if index < self.new.len() {
+            self.new.get_arr(index)
+        } else {
+            self.prev.get_arr(index - self.new.len())
         }
     }
 
@@ -463,6 +600,7 @@ pub struct Environment<'a> {
     pub buffers: &'a mut dyn Buffering,
     pub writers: &'a mut Writers,
     pub scope: &'a mut dyn Scoping,
+    pub runtime: &'a mut Runtime,
 }
 
 pub struct Scope<'a> {
@@ -474,6 +612,10 @@ impl<'a> Scope<'a> {
     pub fn new(parent: &'a mut dyn Scoping) -> Self {
         Scope { parent, scope: PrimitiveScope::new() }
     }
+
+    pub fn with(parent: &'a mut dyn Scoping, scope: PrimitiveScope) -> Self {
+        Scope { parent, scope }
+    }
 }
 
 impl Scoping for Scope<'_> {
@@ -490,7 +632,7 @@ impl Scoping for Scope<'_> {
         }
     }
     fn insert(&mut self, key: String, value: CreateAny) -> Option<CreateAny> {
-        if self.parent.contains_key(&key) {
+        if !self.scope.contains_key(&key) && self.parent.contains_key(&key) {
             self.parent.insert(key, value)
         } else {
             self.scope.insert(key, value)
@@ -502,6 +644,12 @@ impl Scoping for Scope<'_> {
     fn insert_locally(&mut self, key: String, value: CreateAny) -> Option<CreateAny> {
         self.scope.insert(key, value)
     }
+    fn get_globally(&mut self, key: &String) -> Result<&mut CreateAny, CreateError> {
+        self.parent.get_globally(key)
+    }
+    fn get_locally(&mut self, key: &String) -> Result<&mut CreateAny, CreateError> {
+        self.scope.get_mut(key).ok_or(CreateError { code: 6, message: format!("Could not find local named buffer {}", key) })
+    }
     fn contains_key(&self, key: &String) -> bool {
         self.scope.contains_key(key) || self.parent.contains_key(key)
     }
@@ -554,6 +702,26 @@ pub fn resolve_identifier_immut<'a>(in_identifier: &Identifier, scope: &'a dyn S
     Err(CreateError { code: 13, message: format!("{:?} did not return an appropriate value.", in_identifier)})
 }
 
+pub fn resolve_identifier_globally<'a>(in_identifier: &Identifier, scope: &'a mut dyn Scoping) -> Result<&'a mut CreateAny, CreateError> {
+    let mut identifier = in_identifier.clone();
+    let root = scope.get_globally(&identifier.pop().unwrap())?;
+    if identifier.is_empty() {return Ok(root)}
+    match root {
+        CreateAny::SCP(s) => resolve_identifier(&identifier, s),
+        _ => Err(CreateError { code: 3, message: "Identifier in long identifier did not return scope".to_string() }),
+    }
+}
+
+pub fn resolve_identifier_locally<'a>(in_identifier: &Identifier, scope: &'a mut dyn Scoping) -> Result<&'a mut CreateAny, CreateError> {
+    let mut identifier = in_identifier.clone();
+    let root = scope.get_locally(&identifier.pop().unwrap())?;
+    if identifier.is_empty() {return Ok(root)}
+    match root {
+        CreateAny::SCP(s) => resolve_identifier(&identifier, s),
+        _ => Err(CreateError { code: 3, message: "Identifier in long identifier did not return scope".to_string() }),
+    }
+}
+
 pub fn insert_at_identifier<'a>(mut identifier: Identifier, val: CreateAny, scope: &'a mut dyn Scoping) -> Result<Option<CreateAny>, CreateError> {
     let final_identifier = identifier.pop().unwrap();
     
@@ -593,12 +761,65 @@ pub fn insert_at_identifier_locally<'a>(mut identifier: Identifier, val: CreateA
     }
 }
 
+pub fn resolve_index(index: Buffer, len: usize, n: &Identifier) -> Result<usize, CreateError> {
+    resolve_offset(index, len, len, &format!("array {:?}", n))
+}
+
+/// Resolves an index which counts back from the end of an array of `len` elements when negative, accepting positions below `bound`.
+pub fn resolve_offset(index: Buffer, len: usize, bound: usize, target: &str) -> Result<usize, CreateError> {
+    if index.fract() != 0. || !index.is_finite() {
+        return Err(CreateError { code: 7, message: format!("Index {} into {} is not an integer", index, target) });
+    }
+    let resolved = if index < 0. {index + len as Buffer} else {index};
+    if resolved < 0. || resolved >= bound as Buffer {
+        return Err(CreateError { code: 4, message: format!("Index {} is outside of {} of length {}", index, target, len) });
+    }
+    Ok(resolved as usize)
+}
+
+pub fn write_array_index(n: &Identifier, indices: &[MutableBuffer], value: &MutableBuffer, environment: &mut Environment, lossy: bool, resolve: for<'a> fn(&Identifier, &'a mut dyn Scoping) -> Result<&'a mut CreateAny, CreateError>) -> CreateResult {
+    let mut evaluated = Vec::new();
+    for mutbuffer in indices {
+        evaluated.push(match mutbuffer.eval_clone_return(environment, lossy) {
+            Ok(Some(v)) => match *v {
+                CreateAny::BUF(b) => b,
+                _ => return CreateResult::Err(CreateError { code: 3, message: "Index in array assignment did not return buffer".to_string() }),
+            },
+            Ok(None) => return CreateResult::Err(CreateError { code: 3, message: "Index in array assignment cannot be null".to_string() }),
+            Err(e) => return CreateResult::Err(e),
+        });
+    }
+    let val = match value.eval_clone_return(environment, lossy) {
+        Ok(Some(v)) => *v,
+        Ok(None) => return CreateResult::Err(CreateError { code: 3, message: "Array elements cannot be set to null.".to_string() }),
+        Err(e) => return CreateResult::Err(e),
+    };
+    let mut current = match resolve(n, environment.scope) {
+        Ok(v) => v,
+        Err(e) => return CreateResult::Err(e),
+    };
+    for index in evaluated {
+        let arr = match current {
+            CreateAny::ARR(a) => a,
+            _ => return CreateResult::Err(CreateError { code: 3, message: format!("Identifier {:?} was not an array at every level of the assignment", n) }),
+        };
+        current = match resolve_index(index, arr.len(), n) {
+            Ok(i) => &mut arr[i],
+            Err(e) => return CreateResult::Err(e),
+        };
+    }
+    *current = val;
+    CreateResult::Ok()
+}
+
 pub fn write(environment: &mut Environment, val: CreateAny, lossy: bool) -> CreateResult {
     if environment.writers.is_empty() {
         environment.buffers.push(val);
         CreateResult::Ok()
     } else {
-        environment.writers[0].borrow_mut().write_buffer(val);
+        if let CreateResult::Err(e) = environment.writers[0].borrow_mut().write_buffer(val) {
+            return CreateResult::Err(e);
+        }
         if match environment.writers[0].borrow_mut().is_full() {
             Ok(v) => v,
             Err(e) => return CreateResult::Err(e),
@@ -608,20 +829,20 @@ pub fn write(environment: &mut Environment, val: CreateAny, lossy: bool) -> Crea
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            write(environment, val, lossy);
+            return write(environment, val, lossy);
         }
         CreateResult::Ok()
     }
 }
 
-pub fn write_token(tokens: &mut Vec<Token>, environment: &mut Environment) -> CreateResult {
-    match read_token(tokens) {
-        Ok(d) => run_directive(d, tokens, environment, true),
+pub fn write_token(tokens: &mut Vec<Token>, environment: &mut Environment, context: &mut ParseContext) -> CreateResult {
+    match read_token(tokens, context) {
+        Ok(d) => run_directive(d, tokens, environment, true, context),
         Err(e) => CreateResult::Err(e),
     }
 }
 
-pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, environment: &mut Environment, lossy: bool) -> CreateResult {
+pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, environment: &mut Environment, lossy: bool, context: &mut ParseContext) -> CreateResult {
     use CreateDirective::*;
     match directive {
         READ_BUF() => {
@@ -652,10 +873,10 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
                 Ok(_) => return CreateResult::Err(CreateError { code: 3, message: format!("Identifier {:?} was not an array as expected.", n) }),
                 Err(e) => return CreateResult::Err(e),
             };
-            let val = match arr.get(index as usize) {
-                Some(v) => v,
-                None => return CreateResult::Err(CreateError { code: 3, message: format!("Value at index {} in array {:?} was outside of the array", index, n) })
-            }.clone();
+            let val = match resolve_index(index, arr.len(), &n) {
+                Ok(i) => arr[i].clone(),
+                Err(e) => return CreateResult::Err(e),
+            };
             write(environment, val, lossy)
         },
         READ_LIA(n, mut m) => {
@@ -676,11 +897,15 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
                     Ok(None) => return CreateResult::Err(CreateError { code: 3, message: "Index in long array index cannot be null".to_string() }),
                     Err(e) => return CreateResult::Err(e),
                 };
+                let index = match resolve_index(index, arr.len(), &n) {
+                    Ok(i) => i,
+                    Err(e) => return CreateResult::Err(e),
+                };
                 if let None = m.last() {
-                    val = arr.get(index as usize);
+                    val = arr.get(index);
                     break;
                 }
-                arr = match arr.get(index as usize) {
+                arr = match arr.get(index) {
                     Some(CreateAny::ARR(a)) => a.clone(),
                     Some(_) => return CreateResult::Err(CreateError { code: 3, message: "Non-final index in long array index did not resolve to array".to_string() }),
                     None => return CreateResult::Err(CreateError { code: 3, message: "Index in long array index could not be resolved".to_string() }),
@@ -725,11 +950,11 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
             CreateResult::Ok()
         },
         WRITE_NBF(n) => {
-            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1)) {
+            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1), context) {
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -739,11 +964,11 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
             }, environment.scope).into()
         },
         WRITE_NAR(n) => {
-            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1)) {
+            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1), context) {
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -753,7 +978,7 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
             }, environment.scope).into()
         },
         WRITE_NFN(n) => {
-           let mut mutbuffer = match read_mutable_buffer(tokens, None) {
+           let mut mutbuffer = match read_mutable_buffer(tokens, None, context) {
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
@@ -770,7 +995,7 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
             insert_at_identifier(n, CreateAny::FUN(scope), environment.scope).into()
         }
         WRITE_NSC(n) => {
-            let mut mutbuffer = match read_mutable_buffer(tokens, None) {
+            let mut mutbuffer = match read_mutable_buffer(tokens, None, context) {
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
@@ -787,11 +1012,11 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
             insert_at_identifier(n, CreateAny::SCP(scope), environment.scope).into()
         },
         WRITE_GNB(n) => {
-            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1)) {
+            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1), context) {
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -801,11 +1026,11 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
             }, environment.scope).into()
         },
         WRITE_GNA(n) => {
-            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1)) {
+            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1), context) {
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -815,11 +1040,11 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
             }, environment.scope).into()
         },
         WRITE_LNB(n) => {
-            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1)) {
+            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1), context) {
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -829,11 +1054,11 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
             }, environment.scope).into()
         },
         WRITE_LNA(n) => {
-            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1)) {
+            let mut mutbuffer = match read_mutable_buffer(tokens, Some(1), context) {
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -842,11 +1067,28 @@ pub fn run_directive(directive: CreateDirective, tokens: &mut Vec<Token>, enviro
                 None => return CreateResult::Err(CreateError { code: 3, message: "Named buffer was attempted to be set to null.".to_string() })
             }, environment.scope).into()
         },
+        WRITE_DST(t) => {
+            let mutbuffer = match read_mutable_buffer(tokens, None, context) {
+                Ok(v) => v,
+                Err(e) => return CreateResult::Err(e),
+            };
+            write_destructured(&t, &mutbuffer, environment, lossy)
+        },
+        WRITE_IAR(n, i, m) => write_array_index(&n, &i, &m, environment, lossy, resolve_identifier),
+        WRITE_GIR(n, i, m) => write_array_index(&n, &i, &m, environment, lossy, resolve_identifier_globally),
+        WRITE_LIR(n, i, m) => write_array_index(&n, &i, &m, environment, lossy, resolve_identifier_locally),
         CONTROL(c) => {
             c.borrow_mut().run(environment, lossy)
         },
-        BREAK() => CreateResult::Err(CreateError { code: 11, message: "Found misplaced break statement".to_string() }),
-        RETURN() => CreateResult::Err(CreateError { code: 12, message: "Found misplaced return statement".to_string() }),
+        BREAK(l) => {
+            environment.runtime.loop_label = l.clone();
+            CreateResult::Err(CreateError { code: 11, message: "Found misplaced break statement".to_string() })
+        },
+        CONTINUE(l) => {
+            environment.runtime.loop_label = l.clone();
+            CreateResult::Err(CreateError { code: 23, message: "Found continue statement outside of a loop".to_string() })
+        },
+        RETURN(_) => CreateResult::Err(CreateError { code: 12, message: "Found misplaced return statement".to_string() }),
         REMOVE_BUF() => {
             environment.buffers.pop();
             CreateResult::Ok()
@@ -896,10 +1138,10 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
                 Ok(_) => return CreateResult::Err(CreateError { code: 3, message: format!("Identifier {:?} was not an array as expected.", n) }),
                 Err(e) => return CreateResult::Err(e),
             };
-            let val = match arr.get(index as usize) {
-                Some(v) => v,
-                None => return CreateResult::Err(CreateError { code: 3, message: format!("Value at index {} in array {:?} was outside of the array", index, n) })
-            }.clone();
+            let val = match resolve_index(index, arr.len(), &n) {
+                Ok(i) => arr[i].clone(),
+                Err(e) => return CreateResult::Err(e),
+            };
             environment.buffers.pop();
             write(environment, val, lossy)
         },
@@ -921,11 +1163,15 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
                     Ok(None) => return CreateResult::Err(CreateError { code: 3, message: "Index in long array index cannot be null".to_string() }),
                     Err(e) => return CreateResult::Err(e),
                 };
+                let index = match resolve_index(index, arr.len(), &n) {
+                    Ok(i) => i,
+                    Err(e) => return CreateResult::Err(e),
+                };
                 if let None = m.last() {
-                    val = arr.get(index as usize);
+                    val = arr.get(index);
                     break;
                 }
-                arr = match arr.get(index as usize) {
+                arr = match arr.get(index) {
                     Some(CreateAny::ARR(a)) => a.clone(),
                     Some(_) => return CreateResult::Err(CreateError { code: 3, message: "Non-final index in long array index did not resolve to array".to_string() }),
                     None => return CreateResult::Err(CreateError { code: 3, message: "Index in long array index could not be resolved".to_string() }),
@@ -969,7 +1215,7 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -1033,7 +1279,7 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -1047,7 +1293,7 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -1061,7 +1307,7 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -1075,7 +1321,7 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
                 Ok(v) => v,
                 Err(e) => return CreateResult::Err(e),
             };
-            match mutbuffer.evaluate(environment, lossy) {
+            match mutbuffer.evaluate_single(environment, lossy) {
                 CreateResult::Ok() => (),
                 CreateResult::Err(e) => return CreateResult::Err(e),
             }
@@ -1084,11 +1330,40 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
                 None => return CreateResult::Err(CreateError { code: 3, message: "Named buffer was attempted to be set to null.".to_string() })
             }, environment.scope).into()
         },
+        WRITE_DST(t) => {
+            let mutbuffer = match read_mutable_buffer_tokenless(directives) {
+                Ok(v) => v,
+                Err(e) => return CreateResult::Err(e),
+            };
+            write_destructured(&t, &mutbuffer, environment, lossy)
+        },
+        WRITE_IAR(n, i, m) => write_array_index(&n, &i, &m, environment, lossy, resolve_identifier),
+        WRITE_GIR(n, i, m) => write_array_index(&n, &i, &m, environment, lossy, resolve_identifier_globally),
+        WRITE_LIR(n, i, m) => write_array_index(&n, &i, &m, environment, lossy, resolve_identifier_locally),
         CONTROL(c) => {
             c.borrow_mut().run(environment, lossy)
         },
-        BREAK() => CreateResult::Err(CreateError { code: 11, message: "Found misplaced break statement".to_string() }),
-        RETURN() => CreateResult::Err(CreateError { code: 12, message: "Found misplaced return statement".to_string() }),
+        BREAK(l) => {
+            environment.runtime.loop_label = l.clone();
+            CreateResult::Err(CreateError { code: 11, message: "Found misplaced break statement".to_string() })
+        },
+        CONTINUE(l) => {
+            environment.runtime.loop_label = l.clone();
+            CreateResult::Err(CreateError { code: 23, message: "Found continue statement outside of a loop".to_string() })
+        },
+        RETURN(m) => {
+            let mut values = Vec::new();
+            for mutbuffer in &m {
+                match mutbuffer.eval_clone_returns(environment, lossy) {
+                    Ok(v) if v.is_empty() => values.push(CreateAny::NUL()),
+                    Ok(v) => values.extend(v),
+                    Err(e) => return CreateResult::Err(e),
+                }
+            }
+            if values.is_empty() {values.push(CreateAny::NUL())}
+            environment.runtime.return_value = Some(values);
+            CreateResult::Err(CreateError { code: 12, message: "Found misplaced return statement".to_string() })
+        },
         REMOVE_BUF() => {
             environment.buffers.pop();
             CreateResult::Ok()
@@ -1096,7 +1371,7 @@ pub fn run_directive_tokenless(directives: &mut Vec<CreateDirective>, environmen
     }
 }
 
-pub fn read_token(tokens: &mut Vec<Token>) -> Result<CreateDirective, CreateError> {
+pub fn read_token(tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<CreateDirective, CreateError> {
     use Token::*;
     use Command::*;
     use Special::*;
@@ -1108,44 +1383,113 @@ pub fn read_token(tokens: &mut Vec<Token>) -> Result<CreateDirective, CreateErro
     match token {
         CMD(cmd) => {
             Ok(CreateDirective::WRITE_INS(match cmd {
-                ADD => Rc::new(RefCell::new(BinaryOp::new(|l,r| {l+r}))),
-                SUB => Rc::new(RefCell::new(BinaryOp::new(|l,r| {l-r}))),
-                MUL => Rc::new(RefCell::new(BinaryOp::new(|l,r| {l*r}))),
-                DIV => Rc::new(RefCell::new(BinaryOp::new(|l,r| {l/r}))),
-                MOD => Rc::new(RefCell::new(BinaryOp::new(|l,r| {l%r}))),
-                POW => Rc::new(RefCell::new(BinaryOp::new(|l,r| {l.powf(r)}))),
-                SIN => Rc::new(RefCell::new(UnaryOp::new(|v| {v.sin()}))),
-                COS => Rc::new(RefCell::new(UnaryOp::new(|v| {v.cos()}))),
-                TAN => Rc::new(RefCell::new(UnaryOp::new(|v| {v.tan()}))),
-                ASN => Rc::new(RefCell::new(UnaryOp::new(|v| {v.asin()}))),
-                ACS => Rc::new(RefCell::new(UnaryOp::new(|v| {v.acos()}))),
-                ATN => Rc::new(RefCell::new(UnaryOp::new(|v| {v.atan()}))),
-                SQT => Rc::new(RefCell::new(UnaryOp::new(|v| {v.sqrt()}))),
-                CBT => Rc::new(RefCell::new(UnaryOp::new(|v| {v.cbrt()}))),
-                EQU => Rc::new(RefCell::new(BinaryOp::new(|l,r| {
+                ADD => Rc::new(RefCell::new(BinaryOp::new("+", |l,r| {l+r}))),
+                SUB => Rc::new(RefCell::new(BinaryOp::new("-", |l,r| {l-r}))),
+                MUL => Rc::new(RefCell::new(BinaryOp::new("*", |l,r| {l*r}))),
+                DIV => Rc::new(RefCell::new(BinaryOp::new("/", |l,r| {l/r}))),
+                MOD => Rc::new(RefCell::new(BinaryOp::new("%", |l,r| {l%r}))),
+                POW => Rc::new(RefCell::new(BinaryOp::new("^", |l,r| {l.powf(r)}))),
+                SIN => Rc::new(RefCell::new(UnaryOp::new("sin", |v| {v.sin()}))),
+                COS => Rc::new(RefCell::new(UnaryOp::new("cos", |v| {v.cos()}))),
+                TAN => Rc::new(RefCell::new(UnaryOp::new("tan", |v| {v.tan()}))),
+                ASN => Rc::new(RefCell::new(UnaryOp::new("asin", |v| {v.asin()}))),
+                ACS => Rc::new(RefCell::new(UnaryOp::new("acos", |v| {v.acos()}))),
+                ATN => Rc::new(RefCell::new(UnaryOp::new("atan", |v| {v.atan()}))),
+                SQT => Rc::new(RefCell::new(UnaryOp::new("sqrt", |v| {v.sqrt()}))),
+                CBT => Rc::new(RefCell::new(UnaryOp::new("cbrt", |v| {v.cbrt()}))),
+                SNH => Rc::new(RefCell::new(UnaryOp::new("sinh", |v| {v.sinh()}))),
+                CSH => Rc::new(RefCell::new(UnaryOp::new("cosh", |v| {v.cosh()}))),
+                TNH => Rc::new(RefCell::new(UnaryOp::new("tanh", |v| {v.tanh()}))),
+                ASH => Rc::new(RefCell::new(UnaryOp::new("asinh", |v| {v.asinh()}))),
+                ACH => Rc::new(RefCell::new(UnaryOp::new("acosh", |v| {v.acosh()}))),
+                ATH => Rc::new(RefCell::new(UnaryOp::new("atanh", |v| {v.atanh()}))),
+                AT2 => Rc::new(RefCell::new(BinaryOp::new("atan2", |l,r| {l.atan2(r)}))),
+                HYP => Rc::new(RefCell::new(BinaryOp::new("hypot", |l,r| {l.hypot(r)}))),
+                LGN => Rc::new(RefCell::new(UnaryOp::new("ln", |v| {v.ln()}))),
+                LGT => Rc::new(RefCell::new(UnaryOp::new("log10", |v| {v.log10()}))),
+                LGB => Rc::new(RefCell::new(UnaryOp::new("log2", |v| {v.log2()}))),
+                EXP => Rc::new(RefCell::new(UnaryOp::new("exp", |v| {v.exp()}))),
+                ABS => Rc::new(RefCell::new(UnaryOp::new("abs", |v| {v.abs()}))),
+                FLR => Rc::new(RefCell::new(UnaryOp::new("floor", |v| {v.floor()}))),
+                CIL => Rc::new(RefCell::new(UnaryOp::new("ceil", |v| {v.ceil()}))),
+                RND => Rc::new(RefCell::new(UnaryOp::new("round", |v| {v.round()}))),
+                TRC => Rc::new(RefCell::new(UnaryOp::new("trunc", |v| {v.trunc()}))),
+                SGN => Rc::new(RefCell::new(UnaryOp::new("sign", |v| {
+                    if v == 0. {0.} else {v.signum()}
+                }))),
+                MIN => Rc::new(RefCell::new(BinaryOp::new("min", |l,r| {l.min(r)}))),
+                MAX => Rc::new(RefCell::new(BinaryOp::new("max", |l,r| {l.max(r)}))),
+                CLP => Rc::new(RefCell::new(TernaryOp::new("clamp", |v,lo,hi| {v.max(lo).min(hi)}))),
+                BND => Rc::new(RefCell::new(IntegerBinaryOp::new("band", |l,r| {Some(l & r)}))),
+                BOR => Rc::new(RefCell::new(IntegerBinaryOp::new("bor", |l,r| {Some(l | r)}))),
+                BXR => Rc::new(RefCell::new(IntegerBinaryOp::new("bxor", |l,r| {Some(l ^ r)}))),
+                BNT => Rc::new(RefCell::new(IntegerUnaryOp::new("bnot", |v| {Some(!v)}))),
+                SHL => Rc::new(RefCell::new(IntegerBinaryOp::new("shl", |l,r| {l.checked_shl(u32::try_from(r).ok()?)}))),
+                SHR => Rc::new(RefCell::new(IntegerBinaryOp::new("shr", |l,r| {l.checked_shr(u32::try_from(r).ok()?)}))),
+                IDV => Rc::new(RefCell::new(IntegerBinaryOp::new("idiv", |l,r| {l.checked_div_euclid(r)}))),
+                EMD => Rc::new(RefCell::new(IntegerBinaryOp::new("emod", |l,r| {l.checked_rem_euclid(r)}))),
+                LEN => Rc::new(RefCell::new(ValueOp::new("len", 1, array_len))),
+                PSH => Rc::new(RefCell::new(ValueOp::new("push", 2, array_push))),
+                PSF => Rc::new(RefCell::new(ValueOp::new("pushf", 2, array_push_front))),
+                POP => Rc::new(RefCell::new(ValueOp::new("pop", 1, array_pop))),
+                POF => Rc::new(RefCell::new(ValueOp::new("popf", 1, array_pop_front))),
+                INS => Rc::new(RefCell::new(ValueOp::new("insert", 3, array_insert))),
+                REM => Rc::new(RefCell::new(ValueOp::new("remove", 2, array_remove))),
+                SLC => Rc::new(RefCell::new(ValueOp::new("slice", 3, array_slice))),
+                CCT => Rc::new(RefCell::new(ValueOp::new("concat", 2, array_concat))),
+                REV => Rc::new(RefCell::new(ValueOp::new("reverse", 1, array_reverse))),
+                IDX => Rc::new(RefCell::new(ValueOp::new("indexof", 2, array_index_of))),
+                CON => Rc::new(RefCell::new(ValueOp::new("contains", 2, array_contains))),
+                SRT => Rc::new(RefCell::new(ValueOp::new("sort", 1, array_sort))),
+                MAP => Rc::new(RefCell::new(ValueOp::new("map", 2, array_map))),
+                FLT => Rc::new(RefCell::new(ValueOp::new("filter", 2, array_filter))),
+                FLD => Rc::new(RefCell::new(ValueOp::new("fold", 3, array_fold))),
+                RDC => Rc::new(RefCell::new(ValueOp::new("reduce", 2, array_reduce))),
+                ANY => Rc::new(RefCell::new(ValueOp::new("any", 2, array_any))),
+                ALL => Rc::new(RefCell::new(ValueOp::new("all", 2, array_all))),
+                FND => Rc::new(RefCell::new(ValueOp::new("find", 2, array_find))),
+                SRB => Rc::new(RefCell::new(ValueOp::new("sortby", 2, array_sort_by))),
+                RNG => Rc::new(RefCell::new(ValueOp::new("range", 3, array_range))),
+                EQU => Rc::new(RefCell::new(BinaryOp::new("==", |l,r| {
                     if l == r {1.} else {0.}
                 }))),
-                NOT => Rc::new(RefCell::new(UnaryOp::new(|l| {
-                    if l != 0. {0.} else {1.}
+                NOT => Rc::new(RefCell::new(UnaryOp::new("!", |l| {
+                    if is_truthy(l) {0.} else {1.}
                 }))),
-                GTH => Rc::new(RefCell::new(BinaryOp::new(|l,r| {
+                GTH => Rc::new(RefCell::new(BinaryOp::new(">", |l,r| {
                     if l > r {1.} else {0.}
                 }))),
-                LTH => Rc::new(RefCell::new(BinaryOp::new(|l,r| {
+                LTH => Rc::new(RefCell::new(BinaryOp::new("<", |l,r| {
                     if l < r {1.} else {0.}
                 }))),
-                ORR => Rc::new(RefCell::new(BinaryOp::new(|l,r| {
-                    if l == 1. || r == 1. {1.} else {0.}
+                NEQ => Rc::new(RefCell::new(BinaryOp::new("!=", |l,r| {
+                    if l != r {1.} else {0.}
                 }))),
-                AND => Rc::new(RefCell::new(BinaryOp::new(|l,r| {
-                    if l == 1. && r == 1. {1.} else {0.}
+                GTE => Rc::new(RefCell::new(BinaryOp::new(">=", |l,r| {
+                    if l >= r {1.} else {0.}
                 }))),
-                PNT => Rc::new(RefCell::new(UnaryOp::new(|v| {print!("{}", v); v}))),
-                PTC => Rc::new(RefCell::new(UnaryOp::new(|v| {
-                    let tv = v.trunc();
-                    print!("{}",(tv as u8) as char);
-                    tv
+                LTE => Rc::new(RefCell::new(BinaryOp::new("<=", |l,r| {
+                    if l <= r {1.} else {0.}
                 }))),
+                XOR => Rc::new(RefCell::new(BinaryOp::new("xor", |l,r| {
+                    if is_truthy(l) != is_truthy(r) {1.} else {0.}
+                }))),
+                PNT => Rc::new(RefCell::new(UnaryOp::new(".", |v| {print!("{}", v); v}))),
+                PTC => Rc::new(RefCell::new(ValueOp::new(",", 1, print_char))),
+                PRV => Rc::new(RefCell::new(ValueOp::new("print", 1, print_value))),
+                PRL => Rc::new(RefCell::new(ValueOp::new("println", 1, print_line))),
+                PRT => Rc::new(RefCell::new(ValueOp::new("prints", 1, print_text))),
+                FMT => Rc::new(RefCell::new(ValueOp::new("format", 2, format_text))),
+                PRF => Rc::new(RefCell::new(ValueOp::new("printf", 2, print_format))),
+                ENV => Rc::new(RefCell::new(ValueOp::new("getenv", 1, get_env))),
+                EXT => Rc::new(RefCell::new(ValueOp::new("exit", 1, exit_program))),
+                THW => Rc::new(RefCell::new(ValueOp::new("throw", 1, throw_value))),
+                RFL => Rc::new(RefCell::new(ValueOp::new("readfile", 1, read_file))),
+                RFS => Rc::new(RefCell::new(ValueOp::new("readlines", 1, read_file_lines))),
+                WFL => Rc::new(RefCell::new(ValueOp::new("writefile", 2, write_file))),
+                AFL => Rc::new(RefCell::new(ValueOp::new("appendfile", 2, append_file))),
+                EXS => Rc::new(RefCell::new(ValueOp::new("exists", 1, file_exists))),
+                LSD => Rc::new(RefCell::new(ValueOp::new("listdir", 1, list_dir))),
             }))
         },
         NUM(num) => {
@@ -1156,6 +1500,10 @@ pub fn read_token(tokens: &mut Vec<Token>) -> Result<CreateDirective, CreateErro
                 RMB() => Ok(CreateDirective::REMOVE_BUF()),
                 BUF() => Ok(CreateDirective::READ_BUF()),
                 IBF(i) => Ok(CreateDirective::READ_IBF(i)),
+                SNB(n) if is_setter(tokens.last()) => read_destructuring((CreateType::BUF, n), tokens),
+                SNA(n) if is_setter(tokens.last()) => read_destructuring((CreateType::ARR, n), tokens),
+                SNS(n) if is_setter(tokens.last()) => read_destructuring((CreateType::SCP, n), tokens),
+                SNF(n) if is_setter(tokens.last()) => read_destructuring((CreateType::FUN, n), tokens),
                 SNB(n) => Ok(CreateDirective::WRITE_NBF(n)),
                 SNA(n) => Ok(CreateDirective::WRITE_NAR(n)),
                 SNS(n) => Ok(CreateDirective::WRITE_NSC(n)),
@@ -1165,11 +1513,23 @@ pub fn read_token(tokens: &mut Vec<Token>) -> Result<CreateDirective, CreateErro
                 SLB(n) => Ok(CreateDirective::WRITE_LNB(n)),
                 GNB(n) => Ok(CreateDirective::READ_NBF(n)),
                 SNF(n) => Ok(CreateDirective::WRITE_NFN(n)),
+                SIA(n) => {
+                    let (indices, value) = read_array_assignment(tokens, context)?;
+                    Ok(CreateDirective::WRITE_IAR(n, indices, value))
+                },
+                SGI(n) => {
+                    let (indices, value) = read_array_assignment(tokens, context)?;
+                    Ok(CreateDirective::WRITE_GIR(n, indices, value))
+                },
+                SLI(n) => {
+                    let (indices, value) = read_array_assignment(tokens, context)?;
+                    Ok(CreateDirective::WRITE_LIR(n, indices, value))
+                },
                 FNC(n) => {
                     let mut parambuffers: Vec<MutableBuffer> = Vec::new();
                     while let Some(token) = tokens.last() {
                         if let SPC(CLR()) = token {tokens.pop(); break}
-                        parambuffers.push(read_mutable_buffer(tokens, None)?)
+                        parambuffers.push(read_mutable_buffer(tokens, None, context)?)
                     }
                     parambuffers.reverse();
                     let fnc = FunctionCall::new(n.clone(), parambuffers);
@@ -1179,24 +1539,24 @@ pub fn read_token(tokens: &mut Vec<Token>) -> Result<CreateDirective, CreateErro
                     let mut scopedbuffers: Vec<MutableBuffer> = Vec::new();
                     while let Some(token) = tokens.last() {
                         if let SPC(CLB()) = token {tokens.pop(); break}
-                        scopedbuffers.push(read_mutable_buffer(tokens, None)?)
+                        scopedbuffers.push(read_mutable_buffer(tokens, None, context)?)
                     }
                     scopedbuffers.reverse();
                     let control = Scoped::new(scopedbuffers);
                     Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(control))))
                 },
-                CLB() => Err(CreateError { code: 3, message: "Unexpected closing bracket.".to_string() }),
+                CLB() => Err(CreateError { code: 2, message: "Unexpected closing bracket.".to_string() }),
                 OPS() => {
                     let mut values: Vec<MutableBuffer> = Vec::new();
                     while let Some(token) = tokens.last() {
                         if let SPC(CLS()) = token {tokens.pop(); break}
-                        values.push(read_mutable_buffer(tokens, None)?);
+                        values.push(read_mutable_buffer(tokens, None, context)?);
                     }
                     Ok(CreateDirective::WRITE_ARR(values))
                 },
-                CLS() => Err(CreateError { code: 3, message: "Unexpected closing square bracket.".to_string() }),
+                CLS() => Err(CreateError { code: 2, message: "Unexpected closing square bracket.".to_string() }),
                 GIA(n) => {
-                    let mutbuffer = read_mutable_buffer(tokens, None)?;
+                    let mutbuffer = read_mutable_buffer(tokens, None, context)?;
                     if let Some(SPC(CLS())) = tokens.last() {
                         tokens.pop();
                         Ok(CreateDirective::READ_IAR(n, mutbuffer))
@@ -1205,76 +1565,287 @@ pub fn read_token(tokens: &mut Vec<Token>) -> Result<CreateDirective, CreateErro
                         mutbuffers.push(mutbuffer);
                         while let Some(token) = tokens.last() {
                             if let SPC(CLS()) = token {tokens.pop(); break}
-                            mutbuffers.push(read_mutable_buffer(tokens, None)?);
+                            mutbuffers.push(read_mutable_buffer(tokens, None, context)?);
                         }
                         mutbuffers.reverse();
                         Ok(CreateDirective::READ_LIA(n, mutbuffers))
                     }
                 },
-                OPR() => Ok(CreateDirective::WRITE_FUN(read_function(tokens)?)),
+                OPR() => Ok(CreateDirective::WRITE_FUN(read_function(tokens, context)?)),
                 CLR() => Err(CreateError { code: 2, message: "Unexpected ) in source".to_string() }),
-                PIP() => Ok(CreateDirective::WRITE_SCP(read_scope(tokens)?)),
+                PIP() => Ok(CreateDirective::WRITE_SCP(read_scope(tokens, context)?)),
+                RNM() => Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(Input::new(InputKind::Number))))),
+                RCH() => Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(Input::new(InputKind::Char))))),
+                RLN() => Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(Input::new(InputKind::Line))))),
+                RAL() => Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(Input::new(InputKind::All))))),
+                EOF() => Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(Input::new(InputKind::Eof))))),
+                IMP(path) => Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(Import::new(path))))),
+                LBL(label) => read_labeled_loop(label, tokens, context),
             }
         },
         CFL(cfl) => {
             match cfl {
                 IFF => {
-                    let condition = read_mutable_buffer(tokens, None)?;
-                    let ifmutbuffer = read_mutable_buffer(tokens, None)?;
+                    let condition = read_mutable_buffer(tokens, None, context)?;
+                    let ifmutbuffer = read_mutable_buffer(tokens, None, context)?;
                     if let Some(CFL(ELS)) = tokens.last() {
                         tokens.pop();
-                        Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(IfElse::new(condition, ifmutbuffer, read_mutable_buffer(tokens, None)?)))))
+                        Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(IfElse::new(condition, ifmutbuffer, read_mutable_buffer(tokens, None, context)?)))))
                     } else {
                         let control = If::new(condition, ifmutbuffer);
                         Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(control))))
                     }
                 },
-                FOR => {
-                    let mut identifier: Option<Identifier> = None;
-                    if let Some(SPC(SNB(i))) = tokens.last() {
-                        identifier = Some(i.clone());
-                        tokens.pop();
-                    }
-                    let condition = read_mutable_buffer(tokens, None)?;
-                    let control = For::new(condition, match identifier {
-                        Some(mut v) => {
-                            if v.len() > 1 {return Err(CreateError { code: 3, message: "Function condition names can only be single layer".to_string() })}
-                            Some(v.pop().unwrap())
-                        },
-                        None => None,
-                    }, read_mutable_buffer(tokens, None)?);
+                FOR | FRN | WHL => read_loop(cfl, None, tokens, context),
+                MCH => read_match(tokens, context),
+                TRY => read_try(tokens, context),
+                AND => {
+                    let control = And::new(read_mutable_buffer(tokens, None, context)?, read_mutable_buffer(tokens, None, context)?);
                     Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(control))))
                 },
-                FRN => {
-                    let mut identifier: Option<Identifier> = None;
-                    if let Some(SPC(SNB(i))) = tokens.last() {
-                        identifier = Some(i.clone());
-                        tokens.pop();
-                    }
-                    let array = read_mutable_buffer(tokens, None)?;
-                    let control = ForIn::new(array, match identifier {
-                        Some(mut v) => {
-                            if v.len() > 1 {return Err(CreateError { code: 3, message: "Function condition names can only be single layer".to_string() })}
-                            Some(v.pop().unwrap())
-                        },
-                        None => None,
-                    }, read_mutable_buffer(tokens, None)?);
+                ORR => {
+                    let control = Or::new(read_mutable_buffer(tokens, None, context)?, read_mutable_buffer(tokens, None, context)?);
                     Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(control))))
                 },
-                WHL => {
-                    let control = While::new(read_mutable_buffer(tokens, None)?, read_mutable_buffer(tokens, None)?);
-                    Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(control))))
+                BRK => Ok(CreateDirective::BREAK(read_loop_label(tokens, context)?)),
+                CNT => Ok(CreateDirective::CONTINUE(read_loop_label(tokens, context)?)),
+                RTN => {
+                    let mut values = Vec::new();
+                    for _ in 0..*context.return_counts.last().unwrap_or(&1) {
+                        if matches!(tokens.last(), None | Some(SPC(CLB()))) {break}
+                        values.push(read_mutable_buffer(tokens, None, context)?);
+                    }
+                    Ok(CreateDirective::RETURN(values))
                 },
-                BRK => Ok(CreateDirective::BREAK()),
-                RTN => Ok(CreateDirective::RETURN()),
-                _ => Err(CreateError { code: 3, message: "Unexpected control flow token found".to_string() }),
+                _ => Err(CreateError { code: 2, message: "Unexpected control flow token found".to_string() }),
             }
         },
         TYP(..) => Err(CreateError { code: 2, message: "Unexpected type statement found".to_string() }),
     }
 }
 
-pub fn read_mutable_buffer(tokens: &mut Vec<Token>, capacity: Option<i32>) -> Result<MutableBuffer, CreateError> {
+fn read_loop(cfl: ControlFlow, label: Option<String>, tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<CreateDirective, CreateError> {
+    use Token::*;
+    use Special::*;
+    use ControlFlow::*;
+    let control: Rc<RefCell<dyn Controller>> = match cfl {
+        FOR => {
+            let mut identifier: Option<Identifier> = None;
+            if let Some(SPC(SNB(i))) = tokens.last() {
+                identifier = Some(i.clone());
+                tokens.pop();
+            }
+            let condition = read_mutable_buffer(tokens, None, context)?;
+            let mut start = None;
+            if let Some(CFL(FRM)) = tokens.last() {
+                tokens.pop();
+                start = Some(read_mutable_buffer(tokens, None, context)?);
+            }
+            let mut step = None;
+            if let Some(CFL(STP)) = tokens.last() {
+                tokens.pop();
+                step = Some(read_mutable_buffer(tokens, None, context)?);
+            }
+            let control = For::new(condition, match identifier {
+                Some(mut v) => {
+                    if v.len() > 1 {return Err(CreateError { code: 2, message: "Function condition names can only be single layer".to_string() })}
+                    Some(v.pop().unwrap())
+                },
+                None => None,
+            }, start, step, read_mutable_buffer(tokens, None, context)?, label);
+            Rc::new(RefCell::new(control))
+        },
+        FRN => {
+            let mut identifier: Option<Identifier> = None;
+            if let Some(SPC(SNB(i))) = tokens.last() {
+                identifier = Some(i.clone());
+                tokens.pop();
+            }
+            let array = read_mutable_buffer(tokens, None, context)?;
+            let control = ForIn::new(array, match identifier {
+                Some(mut v) => {
+                    if v.len() > 1 {return Err(CreateError { code: 2, message: "Function condition names can only be single layer".to_string() })}
+                    Some(v.pop().unwrap())
+                },
+                None => None,
+            }, read_mutable_buffer(tokens, None, context)?, label);
+            Rc::new(RefCell::new(control))
+        },
+        WHL => {
+            let control = While::new(read_mutable_buffer(tokens, None, context)?, read_mutable_buffer(tokens, None, context)?, label);
+            Rc::new(RefCell::new(control))
+        },
+        _ => return Err(CreateError { code: 2, message: "Labels can only be placed on for, forin and while loops".to_string() }),
+    };
+    Ok(CreateDirective::CONTROL(control))
+}
+
+fn read_match(tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<CreateDirective, CreateError> {
+    use Token::*;
+    use Special::*;
+    use ControlFlow::*;
+    let value = read_mutable_buffer(tokens, None, context)?;
+    let exhaustive = matches!(tokens.last(), Some(CFL(EXH)));
+    if exhaustive {tokens.pop();}
+    match tokens.pop() {
+        Some(SPC(OPB())) => (),
+        _ => return Err(CreateError { code: 2, message: "Expected { after match value".to_string() }),
+    }
+    let mut arms = Vec::new();
+    loop {
+        let pattern = match tokens.last() {
+            Some(SPC(CLB())) => {tokens.pop(); break},
+            Some(CFL(ELS)) => {tokens.pop(); MatchPattern::Default},
+            Some(TYP(t)) => {let t = t.clone(); tokens.pop(); MatchPattern::Type(t)},
+            Some(_) => {
+                let low = read_mutable_buffer(tokens, None, context)?;
+                if let Some(CFL(UPT)) = tokens.last() {
+                    tokens.pop();
+                    MatchPattern::Range(low, read_mutable_buffer(tokens, None, context)?)
+                } else {
+                    MatchPattern::Value(low)
+                }
+            },
+            None => return Err(CreateError { code: 2, message: "Expected } to close match".to_string() }),
+        };
+        if tokens.is_empty() {return Err(CreateError { code: 2, message: "Expected a body for match arm".to_string() })}
+        arms.push((pattern, read_mutable_buffer(tokens, None, context)?));
+    }
+    let control = Match::new(value, arms);
+    if exhaustive && !control.is_exhaustive() {
+        return Err(CreateError { code: 2, message: "Exhaustive match must have an else arm or cover buf, arr, fun, scp and non".to_string() });
+    }
+    Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(control))))
+}
+
+fn read_try(tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<CreateDirective, CreateError> {
+    use Token::*;
+    use Special::*;
+    use ControlFlow::*;
+    let mutbuffer = read_mutable_buffer(tokens, None, context)?;
+    let mut identifier = None;
+    let mut catchmutbuffer = None;
+    if let Some(CFL(CAT)) = tokens.last() {
+        tokens.pop();
+        if let Some(SPC(SNB(_) | SNS(_))) = tokens.last() {
+            identifier = match tokens.pop() {
+                Some(SPC(SNB(mut n) | SNS(mut n))) if n.len() == 1 => n.pop(),
+                _ => return Err(CreateError { code: 2, message: "Catch names can only be single layer".to_string() }),
+            };
+        }
+        catchmutbuffer = Some(read_mutable_buffer(tokens, None, context)?);
+    }
+    let mut finallymutbuffer = None;
+    if let Some(CFL(FIN)) = tokens.last() {
+        tokens.pop();
+        finallymutbuffer = Some(read_mutable_buffer(tokens, None, context)?);
+    }
+    if catchmutbuffer.is_none() && finallymutbuffer.is_none() {
+        return Err(CreateError { code: 2, message: "Expected catch or finally after try".to_string() });
+    }
+    Ok(CreateDirective::CONTROL(Rc::new(RefCell::new(Try::new(mutbuffer, identifier, catchmutbuffer, finallymutbuffer)))))
+}
+
+fn read_labeled_loop(label: String, tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<CreateDirective, CreateError> {
+    let cfl = match tokens.pop() {
+        Some(Token::CFL(cfl)) => cfl,
+        _ => return Err(CreateError { code: 2, message: format!("Label @{} must be followed by a loop", label) }),
+    };
+    context.labels.push(label.clone());
+    let result = read_loop(cfl, Some(label), tokens, context);
+    context.labels.pop();
+    result
+}
+
+fn read_loop_label(tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<Option<String>, CreateError> {
+    let label = match tokens.last() {
+        Some(Token::SPC(Special::LBL(l))) => l.clone(),
+        _ => return Ok(None),
+    };
+    tokens.pop();
+    if context.labels.contains(&label) {
+        Ok(Some(label))
+    } else {
+        Err(CreateError { code: 2, message: format!("Unknown loop label @{}", label) })
+    }
+}
+
+pub fn read_function_body(tokens: &mut Vec<Token>, return_count: usize, context: &mut ParseContext) -> Result<MutableBuffer, CreateError> {
+    let labels = std::mem::take(&mut context.labels);
+    context.return_counts.push(return_count);
+    let result = read_mutable_buffer(tokens, None, context);
+    context.return_counts.pop();
+    context.labels = labels;
+    result
+}
+
+fn is_setter(token: Option<&Token>) -> bool {
+    matches!(token, Some(Token::SPC(Special::SNB(_) | Special::SNA(_) | Special::SNS(_) | Special::SNF(_))))
+}
+
+fn read_destructuring(first: (CreateType, Identifier), tokens: &mut Vec<Token>) -> Result<CreateDirective, CreateError> {
+    use Token::*;
+    use Special::*;
+    let mut targets = vec![first];
+    while is_setter(tokens.last()) {
+        targets.push(match tokens.pop() {
+            Some(SPC(SNB(n))) => (CreateType::BUF, n),
+            Some(SPC(SNA(n))) => (CreateType::ARR, n),
+            Some(SPC(SNS(n))) => (CreateType::SCP, n),
+            Some(SPC(SNF(n))) => (CreateType::FUN, n),
+            _ => unreachable!(),
+        });
+    }
+    Ok(CreateDirective::WRITE_DST(targets))
+}
+
+pub fn write_destructured(targets: &[(CreateType, Identifier)], mutbuffer: &MutableBuffer, environment: &mut Environment, lossy: bool) -> CreateResult {
+    let mut exposed_buffers = PartitionedBuffers::new(environment.buffers);
+    if let CreateResult::Err(e) = mutbuffer.evaluate_clone(&mut Environment { buffers: &mut exposed_buffers, writers: &mut Writers::new(), scope: environment.scope, runtime: environment.runtime }, lossy) {
+        return CreateResult::Err(e);
+    }
+    let mut values = exposed_buffers.get_returns();
+    if values.len() == 1 && targets.len() > 1 {
+        values = match values.pop().unwrap() {
+            CreateAny::ARR(a) => a,
+            CreateAny::SCP(s) => {
+                let mut fields = Vec::new();
+                for (_, n) in targets {
+                    match s.get(&n[0]) {
+                        Some(v) => fields.push(v.clone()),
+                        None => return CreateResult::Err(CreateError { code: 24, message: format!("Scope has no field {} to destructure", n[0]) }),
+                    }
+                }
+                fields
+            },
+            v => return CreateResult::Err(CreateError { code: 24, message: format!("Cannot destructure a value of type {:?}", v.get_type()) }),
+        };
+    }
+    if values.len() != targets.len() {
+        return CreateResult::Err(CreateError { code: 24, message: format!("Expected {} values to destructure, found {}", targets.len(), values.len()) });
+    }
+    for ((t, n), v) in targets.iter().zip(values) {
+        if !t.matches(&v) {
+            return CreateResult::Err(CreateError { code: 7, message: format!("Tried to assign {:?} to {:?} destructuring target {:?}", v.get_type(), t, n) });
+        }
+        if let Err(e) = insert_at_identifier(n.clone(), v, environment.scope) {
+            return CreateResult::Err(e);
+        }
+    }
+    CreateResult::Ok()
+}
+
+pub fn read_array_assignment(tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<(Vec<MutableBuffer>, MutableBuffer), CreateError> {
+    let mut indices = Vec::new();
+    while let Some(token) = tokens.last() {
+        if let Token::SPC(Special::CLS()) = token {tokens.pop(); break}
+        indices.push(read_mutable_buffer(tokens, None, context)?);
+    }
+    if indices.is_empty() {return Err(CreateError { code: 2, message: "Array assignment requires at least one index".to_string() })}
+    Ok((indices, read_mutable_buffer(tokens, None, context)?))
+}
+
+pub fn read_mutable_buffer(tokens: &mut Vec<Token>, capacity: Option<i32>, context: &mut ParseContext) -> Result<MutableBuffer, CreateError> {
     let mut mutbuffer = MutableBuffer::new();
     let mut capacity = match capacity {
         Some(v) => vec![v],
@@ -1282,9 +1853,9 @@ pub fn read_mutable_buffer(tokens: &mut Vec<Token>, capacity: Option<i32>) -> Re
     };
     'main: while let Some(_) = tokens.last() {
         use CreateDirective::*;
-        let current = read_token(tokens)?;
+        let current = read_token(tokens, context)?;
         mutbuffer.push(current);
-        match mutbuffer.last().unwrap() {
+        let mut produced = match mutbuffer.last().unwrap() {
             READ_BUF(..)
             | READ_IBF(..)
             | READ_IAR(..)
@@ -1292,23 +1863,11 @@ pub fn read_mutable_buffer(tokens: &mut Vec<Token>, capacity: Option<i32>) -> Re
             | READ_NBF(..)
             | WRITE_BUF(..)
             | WRITE_ARR(..)
-            | WRITE_SCP(..) => {
-                'rec1: loop {
-                    match capacity.last_mut() { 
-                        Some(v) => {
-                            *v -= 1;
-                            if capacity.last().unwrap() <= &0 {
-                                capacity.pop();
-                                continue 'rec1;
-                            }
-                            break 'rec1;
-                        },
-                        None => break 'main,
-                    };
-                }
-            },
+            | WRITE_FUN(..)
+            | WRITE_SCP(..) => 1,
             WRITE_INS(i) => {
-                capacity.push(i.borrow().capacity()? as i32)
+                capacity.push(i.borrow().capacity()? as i32);
+                0
             },
             WRITE_GNB(..)
             | WRITE_GNA(..)
@@ -1316,25 +1875,30 @@ pub fn read_mutable_buffer(tokens: &mut Vec<Token>, capacity: Option<i32>) -> Re
             | WRITE_LNA(..)
             | WRITE_NBF(..)
             | WRITE_NAR(..)
-            | WRITE_NSC(..) => {
+            | WRITE_NSC(..)
+            | WRITE_NFN(..)
+            | WRITE_DST(..) => {
                 capacity.push(1);
+                0
             },
-            CONTROL(c) => {
-                'rec2: loop {
-                    match capacity.last_mut() { 
-                        Some(v) => {
-                            *v -= c.borrow().return_count() as i32;
-                            if capacity.last().unwrap() <= &0 {
-                                capacity.pop();
-                                continue 'rec2;
-                            }
-                            break 'rec2;
-                        },
-                        None => break 'main,
-                    };
+            CONTROL(c) => c.borrow().return_count() as i32,
+            _ => 0,
+        };
+        if produced > 0 {
+            'rec: loop {
+                match capacity.last_mut() {
+                    Some(v) => {
+                        *v -= produced;
+                        if *v <= 0 {
+                            capacity.pop();
+                            produced = 1;
+                            continue 'rec;
+                        }
+                        break 'rec;
+                    },
+                    None => break 'main,
                 }
-            },
-            _ => (),
+            }
         }
         if capacity.is_empty() {break}
     }
@@ -1348,32 +1912,50 @@ pub fn read_mutable_buffer_tokenless<'a>(directives: &mut Vec<CreateDirective>)
     'main: while let Some(directive) = directives.pop() {
         use CreateDirective::*;
         mutbuffer.push(directive);
-        match mutbuffer.last().unwrap() {
+        let mut produced = match mutbuffer.last().unwrap() {
             READ_BUF()
             | READ_IBF(_)
             | READ_NBF(_)
             | READ_IAR(_,_)
-            | WRITE_BUF(_) => {
-                'rec: loop {
-                    match capacity.last_mut() {
-                        Some(v) => {
-                            *v -= 1;
-                            if capacity.last().unwrap() <= &0 {
-                                capacity.pop();
-                                continue 'rec;
-                            }
-                            break 'rec;
-                        },
-                        None => break 'main,
-                    }
-                }
-            },
+            | READ_LIA(_,_)
+            | WRITE_BUF(_)
+            | WRITE_ARR(_)
+            | WRITE_FUN(_)
+            | WRITE_SCP(_) => 1,
+            CONTROL(c) => c.borrow().return_count() as i32,
             WRITE_INS(i) => {
                 capacity.push(i.borrow().capacity()? as i32);
+                0
             },
             WRITE_GNB(_)
-            | WRITE_NBF(_) => capacity.push(1),
-            _ => (),
+            | WRITE_GNA(_)
+            | WRITE_LNB(_)
+            | WRITE_LNA(_)
+            | WRITE_NBF(_)
+            | WRITE_NAR(_)
+            | WRITE_NSC(_)
+            | WRITE_NFN(_)
+            | WRITE_DST(_) => {
+                capacity.push(1);
+                0
+            },
+            _ => 0,
+        };
+        if produced > 0 {
+            'rec: loop {
+                match capacity.last_mut() {
+                    Some(v) => {
+                        *v -= produced;
+                        if *v <= 0 {
+                            capacity.pop();
+                            produced = 1;
+                            continue 'rec;
+                        }
+                        break 'rec;
+                    },
+                    None => break 'main,
+                }
+            }
         }
         if capacity.is_empty() {break}
     }
@@ -1381,23 +1963,94 @@ pub fn read_mutable_buffer_tokenless<'a>(directives: &mut Vec<CreateDirective>)
     Ok(mutbuffer)
 }
 
-pub fn interpret_program(data: Vec<Token>) -> CreateResult {
+pub fn interpret_program(data: Vec<Token>, runtime: &mut Runtime) -> CreateResult {
+    let mut scope: PrimitiveScope = PrimitiveScope::new();
+    if runtime.prelude {
+        if let CreateResult::Err(e) = load_prelude(&mut scope, runtime) {
+            return CreateResult::Err(e);
+        }
+    }
+    scope.insert("args".to_string(), CreateAny::ARR(runtime.args.iter().map(|a| read_argument(a)).collect()));
+    interpret_in_scope(data, &mut scope, runtime)
+}
+
+pub fn interpret_in_scope(data: Vec<Token>, scope: &mut PrimitiveScope, runtime: &mut Runtime) -> CreateResult {
     let mut program = data.clone();
     program.reverse();
     let mut writers: Writers = Writers::new();
     let mut buffers: Buffers = Buffers::new();
-    let mut scope: PrimitiveScope = PrimitiveScope::new();
     let mut environment: Environment = Environment { 
         writers: &mut writers, 
         buffers: &mut buffers, 
-        scope: &mut scope,
+        scope,
+        runtime,
     };
+    let mut context = ParseContext::new();
 
     while let Some(_) = program.last() {
-        match write_token(&mut program, &mut environment) {
+        match write_token(&mut program, &mut environment, &mut context) {
             CreateResult::Ok() => (),
             CreateResult::Err(e) => return CreateResult::Err(e),
         }
     }
     CreateResult::Ok()
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name() -> Identifier {
+        vec!["a".to_string()]
+    }
+
+    #[test]
+    fn resolves_positive_indices() {
+        assert_eq!(resolve_index(0., 3, &name()).ok(), Some(0));
+        assert_eq!(resolve_index(2., 3, &name()).ok(), Some(2));
+    }
+
+    #[test]
+    fn resolves_negative_indices_from_the_end() {
+        assert_eq!(resolve_index(-1., 3, &name()).ok(), Some(2));
+        assert_eq!(resolve_index(-3., 3, &name()).ok(), Some(0));
+    }
+
+    #[test]
+    fn rejects_out_of_range_indices() {
+        assert_eq!(resolve_index(3., 3, &name()).err().map(|e| e.code), Some(4));
+        assert_eq!(resolve_index(-4., 3, &name()).err().map(|e| e.code), Some(4));
+        assert_eq!(resolve_index(0., 0, &name()).err().map(|e| e.code), Some(4));
+    }
+
+    #[test]
+    fn rejects_non_integer_indices() {
+        assert_eq!(resolve_index(1.5, 3, &name()).err().map(|e| e.code), Some(7));
+        assert_eq!(resolve_index(Buffer::NAN, 3, &name()).err().map(|e| e.code), Some(7));
+        assert_eq!(resolve_index(Buffer::INFINITY, 3, &name()).err().map(|e| e.code), Some(7));
+    }
+
+    fn parse(source: &str) -> Result<CreateDirective, CreateError> {
+        let mut tokens = tokenize(source)?;
+        tokens.reverse();
+        read_token(&mut tokens, &mut ParseContext::new())
+    }
+
+    #[test]
+    fn resolves_loop_labels_in_scope() {
+        assert!(parse("@outer for 3 { for 3 { break @outer } }").is_ok());
+        assert_eq!(parse("for 3 { continue @outer }").err().map(|e| e.code), Some(2));
+    }
+
+    #[test]
+    fn function_bodies_cannot_name_outer_loops() {
+        assert_eq!(parse("@outer for 3 { =()f ( ) buf { break @outer } }").err().map(|e| e.code), Some(2));
+        assert!(parse("@outer for 3 { =()f ( ) buf { @inner for 3 { break @inner } } }").is_ok());
+    }
+
+    #[test]
+    fn resolves_offsets_up_to_the_bound() {
+        assert_eq!(resolve_offset(3., 3, 4, "array").ok(), Some(3));
+        assert_eq!(resolve_offset(-1., 3, 4, "array").ok(), Some(2));
+        assert_eq!(resolve_offset(4., 3, 4, "array").err().map(|e| e.code), Some(4));
+    }
+}
@@ -4,4 +4,12 @@ pub mod interpreter;
 pub mod instructions;
 pub mod controllers;
 pub mod functions;
-pub mod utils;
\ No newline at end of file
+pub mod arrays;
+pub mod output;
+pub mod input;
+pub mod system;
+pub mod files;
+pub mod modules;
+pub mod prelude;
+pub mod utils;
+pub mod runtime;
\ No newline at end of file
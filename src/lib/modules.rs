@@ -0,0 +1,105 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use super::interpreter::*;
+use super::errors::*;
+use super::tokenizer::*;
+use super::runtime::*;
+
+#[derive(Clone)]
+pub struct Import {
+    path: String,
+}
+
+impl Import {
+    pub fn new(path: String) -> Self {
+        Import { path }
+    }
+}
+
+impl Controller for Import {
+    fn run(&mut self, environment: &mut Environment, lossy: bool) -> CreateResult {
+        match import_module(&self.path, environment.runtime) {
+            Ok(module) => write(environment, module, lossy),
+            Err(e) => CreateResult::Err(e),
+        }
+    }
+
+    fn clone_cfl(&self) -> Rc<RefCell<dyn Controller>> {
+        Rc::new(RefCell::new(self.clone()))
+    }
+
+    fn return_count(&self) -> usize {1}
+}
+
+fn resolve_module(path: &str, runtime: &Runtime) -> Result<PathBuf, CreateError> {
+    let requested = Path::new(path);
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if requested.is_absolute() {
+        candidates.push(requested.to_path_buf());
+    } else {
+        if let Some(dir) = runtime.import_stack.last().and_then(|f| f.parent()) {
+            candidates.push(dir.join(requested));
+        }
+        candidates.extend(runtime.import_paths.iter().map(|dir| dir.join(requested)));
+    }
+    let resolved = candidates.iter().find_map(|p| p.canonicalize().ok())
+        .ok_or(CreateError { code: 21, message: format!("Could not find module {}", path) })?;
+    let mut roots: Vec<&Path> = runtime.import_stack.first().and_then(|f| f.parent()).into_iter().collect();
+    roots.extend(runtime.import_paths.iter().map(PathBuf::as_path));
+    roots.extend(runtime.allow_read.iter().map(PathBuf::as_path));
+    if roots.iter().any(|dir| resolved.starts_with(dir)) {
+        Ok(resolved)
+    } else {
+        Err(CreateError { code: 20, message: format!("import was not permitted to read {} (see --import-path)", path) })
+    }
+}
+
+pub fn import_module(path: &str, runtime: &mut Runtime) -> Result<CreateAny, CreateError> {
+    let resolved = resolve_module(path, runtime)?;
+    if let Some(module) = runtime.imports.get(&resolved) {
+        return Ok(module.clone());
+    }
+    if runtime.import_stack.contains(&resolved) {
+        let chain = runtime.import_stack.iter().chain(std::iter::once(&resolved)).map(|p| p.display().to_string()).collect::<Vec<String>>();
+        return Err(CreateError { code: 22, message: format!("Circular import: {}", chain.join(" -> ")) });
+    }
+    let source = std::fs::read_to_string(&resolved).map_err(|e| CreateError { code: 21, message: format!("{}: {}", path, e) })?;
+    let tokens = tokenize(&source).map_err(|e| in_module(e, &resolved))?;
+    let mut scope = runtime.prelude_scope.clone();
+    runtime.import_stack.push(resolved.clone());
+    let result = interpret_in_scope(tokens, &mut scope, runtime);
+    runtime.import_stack.pop();
+    if let CreateResult::Err(e) = result {
+        return Err(in_module(e, &resolved));
+    }
+    let module = CreateAny::SCP(scope);
+    runtime.imports.insert(resolved, module.clone());
+    Ok(module)
+}
+
+fn in_module(err: CreateError, path: &Path) -> CreateError {
+    match err.code {
+        19 => err,
+        _ => CreateError { code: err.code, message: format!("{} (in module {})", err.message, path.display()) },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destructures_calls_into_modules() {
+        let dir = std::env::temp_dir().join(format!("create-modules-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("geo.crt"), "=()divmod ( =a =b ) buf buf { return idiv ~a ~b emod ~a ~b }").unwrap();
+        let mut runtime = Runtime::new();
+        runtime.import_paths.push(dir.canonicalize().unwrap());
+        let mut scope = PrimitiveScope::new();
+        let result = interpret_in_scope(tokenize("=||g import \"geo.crt =q =r ~g.divmod( 7 2 )").unwrap(), &mut scope, &mut runtime);
+        assert!(matches!(result, CreateResult::Ok()));
+        assert!(scope.get("q") == Some(&CreateAny::BUF(3.)));
+        assert!(scope.get("r") == Some(&CreateAny::BUF(1.)));
+    }
+}
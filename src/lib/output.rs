@@ -0,0 +1,268 @@
+use super::interpreter::*;
+use super::errors::*;
+
+pub fn read_code_point(name: &str, value: Buffer) -> Result<char, CreateError> {
+    if !value.is_finite() || value < 0. || value > u32::MAX as Buffer {
+        return Err(CreateError { code: 17, message: format!("{} was given {}, which is not a valid char", name, value) });
+    }
+    char::from_u32(value as u32).ok_or(CreateError { code: 17, message: format!("{} was given {:#x}, which is not a valid unicode scalar value", name, value as u32) })
+}
+
+pub fn read_text(name: &str, value: &CreateAny) -> Result<String, CreateError> {
+    let arr = match value {
+        CreateAny::ARR(a) => a,
+        v => return Err(CreateError { code: 10, message: format!("{} expected a char array, found {:?}", name, v.get_type()) }),
+    };
+    let mut text = String::new();
+    for v in arr {
+        match v {
+            CreateAny::BUF(b) if b.fract() != 0. => return Err(CreateError { code: 8, message: format!("{} was given {}, which is not a whole char", name, b) }),
+            CreateAny::BUF(b) => text.push(read_code_point(name, *b)?),
+            v => return Err(CreateError { code: 10, message: format!("{} expected a char array, found an element of type {:?}", name, v.get_type()) }),
+        }
+    }
+    Ok(text)
+}
+
+pub fn write_text(text: &str) -> CreateAny {
+    CreateAny::ARR(text.chars().map(|c| CreateAny::BUF(c as u32 as Buffer)).collect())
+}
+
+pub fn print_char(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let value = match values.into_iter().next().unwrap() {
+        CreateAny::BUF(b) => b.trunc(),
+        v => return Err(CreateError { code: 7, message: format!("char output expected a buffer, found {:?}", v.get_type()) }),
+    };
+    print!("{}", read_code_point("char output", value)?);
+    Ok(CreateAny::BUF(value))
+}
+
+pub fn print_value(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let value = values.into_iter().next().unwrap();
+    print!("{}", value);
+    Ok(value)
+}
+
+pub fn print_line(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let value = values.into_iter().next().unwrap();
+    println!("{}", value);
+    Ok(value)
+}
+
+pub fn print_text(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let value = values.into_iter().next().unwrap();
+    print!("{}", read_text("prints", &value)?);
+    Ok(value)
+}
+
+struct FormatSpec {
+    fill: char,
+    align: Option<char>,
+    plus: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: Option<char>,
+}
+
+fn parse_spec(spec: &str) -> Result<FormatSpec, CreateError> {
+    let invalid = || CreateError { code: 16, message: format!("Invalid format specifier {{:{}}}", spec) };
+    let mut chars = spec.chars().peekable();
+    let mut format_spec = FormatSpec { fill: ' ', align: None, plus: false, zero: false, width: 0, precision: None, kind: None };
+    let lookahead = spec.chars().take(2).collect::<Vec<char>>();
+    if lookahead.len() == 2 && matches!(lookahead[1], '<' | '^' | '>') {
+        format_spec.fill = lookahead[0];
+        format_spec.align = Some(lookahead[1]);
+        chars.next();
+        chars.next();
+    } else if !lookahead.is_empty() && matches!(lookahead[0], '<' | '^' | '>') {
+        format_spec.align = Some(lookahead[0]);
+        chars.next();
+    }
+    if let Some('+') = chars.peek() {
+        format_spec.plus = true;
+        chars.next();
+    }
+    if let Some('0') = chars.peek() {
+        format_spec.zero = true;
+        chars.next();
+    }
+    let mut width = String::new();
+    while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+        width.push(*c);
+        chars.next();
+    }
+    if !width.is_empty() {
+        format_spec.width = width.parse().map_err(|_| invalid())?;
+    }
+    if let Some('.') = chars.peek() {
+        chars.next();
+        let mut precision = String::new();
+        while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            precision.push(*c);
+            chars.next();
+        }
+        format_spec.precision = Some(precision.parse().map_err(|_| invalid())?);
+    }
+    format_spec.kind = chars.next();
+    if chars.next().is_some() {return Err(invalid())}
+    Ok(format_spec)
+}
+
+fn render_value(value: &CreateAny, spec: &FormatSpec) -> Result<String, CreateError> {
+    let (body, numeric) = match value {
+        CreateAny::BUF(b) => (match spec.kind {
+            Some(kind @ ('x' | 'X' | 'b' | 'o')) => {
+                if b.fract() != 0. || !b.is_finite() {
+                    return Err(CreateError { code: 10, message: format!("Format type {} requires an integer, found {}", kind, b) });
+                }
+                let magnitude = (*b as i64).unsigned_abs();
+                let digits = match kind {
+                    'x' => format!("{:x}", magnitude),
+                    'X' => format!("{:X}", magnitude),
+                    'b' => format!("{:b}", magnitude),
+                    _ => format!("{:o}", magnitude),
+                };
+                if *b < 0. {format!("-{}", digits)} else {digits}
+            },
+            Some('e') => match spec.precision {
+                Some(p) => format!("{:.*e}", p, b),
+                None => format!("{:e}", b),
+            },
+            None => match spec.precision {
+                Some(p) => format!("{:.*}", p, b),
+                None => format!("{}", b),
+            },
+            Some(kind) => return Err(CreateError { code: 16, message: format!("Unknown format type {}", kind) }),
+        }, true),
+        v => {
+            if let Some(kind) = spec.kind {
+                return Err(CreateError { code: 10, message: format!("Format type {} cannot be applied to {:?}", kind, v.get_type()) });
+            }
+            (v.to_string(), false)
+        },
+    };
+    let body = if spec.plus && numeric && !body.starts_with('-') {format!("+{}", body)} else {body};
+    let length = body.chars().count();
+    if length >= spec.width {return Ok(body)}
+    let padding = spec.width - length;
+    if spec.zero && numeric && spec.align.is_none() {
+        let sign_length = if body.starts_with(['-', '+']) {1} else {0};
+        let (sign, digits) = body.split_at(sign_length);
+        return Ok(format!("{}{}{}", sign, "0".repeat(padding), digits));
+    }
+    let fill = |n: usize| spec.fill.to_string().repeat(n);
+    Ok(match spec.align.unwrap_or(if numeric {'>'} else {'<'}) {
+        '<' => format!("{}{}", body, fill(padding)),
+        '^' => format!("{}{}{}", fill(padding / 2), body, fill(padding - padding / 2)),
+        _ => format!("{}{}", fill(padding), body),
+    })
+}
+
+pub fn format_template(template: &str, values: &[CreateAny]) -> Result<String, CreateError> {
+    let mut output = String::new();
+    let mut chars = template.chars().peekable();
+    let mut next_value = 0;
+    let mut used = vec![false; values.len()];
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                output.push('{');
+            },
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                output.push('}');
+            },
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(CreateError { code: 16, message: "Unclosed { in format template".to_string() }),
+                    }
+                }
+                let (position, spec) = match placeholder.split_once(':') {
+                    Some((p, s)) => (p, s),
+                    None => (placeholder.as_str(), ""),
+                };
+                let index = if position.is_empty() {
+                    next_value += 1;
+                    next_value - 1
+                } else {
+                    position.parse::<usize>().map_err(|_| CreateError { code: 16, message: format!("Invalid format position {}", position) })?
+                };
+                let value = values.get(index).ok_or(CreateError { code: 16, message: format!("Format template refers to value {}, but only {} were given", index, values.len()) })?;
+                used[index] = true;
+                output.push_str(&render_value(value, &parse_spec(spec)?)?);
+            },
+            '}' => return Err(CreateError { code: 16, message: "Unmatched } in format template".to_string() }),
+            c => output.push(c),
+        }
+    }
+    if let Some(index) = used.iter().position(|u| !u) {
+        return Err(CreateError { code: 16, message: format!("Value {} was never used by the format template", index) });
+    }
+    Ok(output)
+}
+
+fn format_values(name: &str, values: Vec<CreateAny>) -> Result<String, CreateError> {
+    let mut values = values.into_iter();
+    let template = read_text(name, &values.next().unwrap())?;
+    match values.next().unwrap() {
+        CreateAny::ARR(a) => format_template(&template, &a),
+        v => Err(CreateError { code: 10, message: format!("{} expected an array of values, found {:?}", name, v.get_type()) }),
+    }
+}
+
+pub fn format_text(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    Ok(write_text(&format_values("format", values)?))
+}
+
+pub fn print_format(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let text = format_values("printf", values)?;
+    print!("{}", text);
+    Ok(write_text(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(template: &str, values: &[Buffer]) -> Result<String, CreateError> {
+        format_template(template, &values.iter().map(|v| CreateAny::BUF(*v)).collect::<Vec<CreateAny>>())
+    }
+
+    #[test]
+    fn fills_placeholders_in_order() {
+        assert_eq!(format("{} and {}", &[1., 2.5]).ok().as_deref(), Some("1 and 2.5"));
+        assert_eq!(format("{1} before {0}", &[1., 2.]).ok().as_deref(), Some("2 before 1"));
+        assert_eq!(format("{{}} {}", &[3.]).ok().as_deref(), Some("{} 3"));
+    }
+
+    #[test]
+    fn applies_precision_and_alignment() {
+        assert_eq!(format("{:.3}", &[1.23456]).ok().as_deref(), Some("1.235"));
+        assert_eq!(format("[{:>6}]", &[42.]).ok().as_deref(), Some("[    42]"));
+        assert_eq!(format("[{:<6}]", &[42.]).ok().as_deref(), Some("[42    ]"));
+        assert_eq!(format("[{:*^6}]", &[42.]).ok().as_deref(), Some("[**42**]"));
+        assert_eq!(format("{:+05}", &[-7.]).ok().as_deref(), Some("-0007"));
+    }
+
+    #[test]
+    fn formats_integers_in_other_radixes() {
+        assert_eq!(format("{:x} {:X} {:b} {:o}", &[255., 255., 5., 8.]).ok().as_deref(), Some("ff FF 101 10"));
+        assert_eq!(format("{:x}", &[-16.]).ok().as_deref(), Some("-10"));
+        assert_eq!(format("{:x}", &[1.5]).err().map(|e| e.code), Some(10));
+    }
+
+    #[test]
+    fn rejects_malformed_templates() {
+        assert_eq!(format("{", &[]).err().map(|e| e.code), Some(16));
+        assert_eq!(format("}", &[]).err().map(|e| e.code), Some(16));
+        assert_eq!(format("{} {}", &[1.]).err().map(|e| e.code), Some(16));
+        assert_eq!(format("{}", &[1., 2.]).err().map(|e| e.code), Some(16));
+        assert_eq!(format("{:q}", &[1.]).err().map(|e| e.code), Some(16));
+    }
+}
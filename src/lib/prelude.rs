@@ -0,0 +1,58 @@
+use super::interpreter::*;
+use super::errors::*;
+use super::tokenizer::*;
+use super::runtime::*;
+
+pub const PRELUDE: &str = include_str!("prelude.crt");
+
+pub fn load_prelude(scope: &mut PrimitiveScope, runtime: &mut Runtime) -> CreateResult {
+    let tokens = match tokenize(PRELUDE) {
+        Ok(t) => t,
+        Err(e) => return CreateResult::Err(in_prelude(e)),
+    };
+    match interpret_in_scope(tokens, scope, runtime) {
+        CreateResult::Ok() => {
+            runtime.prelude_scope = scope.clone();
+            CreateResult::Ok()
+        },
+        CreateResult::Err(e) => CreateResult::Err(in_prelude(e)),
+    }
+}
+
+fn in_prelude(err: CreateError) -> CreateError {
+    CreateError { code: err.code, message: format!("{} (in prelude)", err.message) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::output::write_text;
+
+    fn run(source: &str) -> PrimitiveScope {
+        let mut scope = PrimitiveScope::new();
+        let mut runtime = Runtime::new();
+        assert!(matches!(load_prelude(&mut scope, &mut runtime), CreateResult::Ok()));
+        assert!(matches!(interpret_in_scope(tokenize(source).unwrap(), &mut scope, &mut runtime), CreateResult::Ok()));
+        scope
+    }
+
+    #[test]
+    fn computes_gcd() {
+        let scope = run("=a ~gcd( 12 18 ) =b ~gcd( 7 5 )");
+        assert!(scope.get("a") == Some(&CreateAny::BUF(6.)));
+        assert!(scope.get("b") == Some(&CreateAny::BUF(1.)));
+    }
+
+    #[test]
+    fn trims_text() {
+        let scope = run("=[]t ~trim( [ 32 32 'h 'i 32 ] )");
+        assert!(scope.get("t") == Some(&write_text("hi")));
+    }
+
+    #[test]
+    fn splits_and_joins_text() {
+        let scope = run("=[]parts ~split( [ \"a,bc,d ] ', ) =[]joined ~join( ~parts [ \"-- ] )");
+        assert!(scope.get("parts") == Some(&CreateAny::ARR(vec![write_text("a"), write_text("bc"), write_text("d")])));
+        assert!(scope.get("joined") == Some(&write_text("a--bc--d")));
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::collections::HashMap;
+use super::interpreter::{CreateAny, PrimitiveScope};
+
+pub struct Runtime {
+    pub strict_math: bool,
+    pub prelude: bool,
+    pub prelude_scope: PrimitiveScope,
+    pub input: Box<dyn BufRead>,
+    pub args: Vec<String>,
+    pub exit_status: Option<u8>,
+    pub allow_read: Vec<PathBuf>,
+    pub allow_write: Vec<PathBuf>,
+    pub import_paths: Vec<PathBuf>,
+    pub import_stack: Vec<PathBuf>,
+    pub imports: HashMap<PathBuf, CreateAny>,
+    pub loop_label: Option<String>,
+    pub return_value: Option<Vec<CreateAny>>,
+    pub thrown: Option<CreateAny>,
+}
+
+impl Runtime {
+    pub fn new() -> Self {
+        Runtime { strict_math: false, prelude: true, prelude_scope: PrimitiveScope::new(), input: Box::new(std::io::stdin().lock()), args: Vec::new(), exit_status: None, allow_read: Vec::new(), allow_write: Vec::new(), import_paths: Vec::new(), import_stack: Vec::new(), imports: HashMap::new(), loop_label: None, return_value: None, thrown: None }
+    }
+}
@@ -0,0 +1,42 @@
+use super::interpreter::*;
+use super::errors::*;
+use super::output::*;
+use super::utils::*;
+use std::io::Write;
+
+pub fn read_argument(arg: &str) -> CreateAny {
+    match read_number(arg) {
+        Some(v) => CreateAny::BUF(v),
+        None => write_text(arg),
+    }
+}
+
+pub fn get_env(values: Vec<CreateAny>, _: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let name = read_text("getenv", &values.into_iter().next().unwrap())?;
+    match std::env::var(&name) {
+        Ok(v) => Ok(write_text(&v)),
+        Err(std::env::VarError::NotPresent) => Ok(CreateAny::NUL()),
+        Err(std::env::VarError::NotUnicode(_)) => Err(CreateError { code: 1, message: format!("Environment variable {} is not valid unicode", name) }),
+    }
+}
+
+pub fn exit_program(values: Vec<CreateAny>, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let status = match values[0] {
+        CreateAny::BUF(v) if v.fract() == 0. && (0. ..=255.).contains(&v) => v as u8,
+        CreateAny::BUF(v) => return Err(CreateError { code: 10, message: format!("exit expected a status between 0 and 255, found {}", v) }),
+        ref v => return Err(CreateError { code: 10, message: format!("exit expected a buffer, found {:?}", v.get_type()) }),
+    };
+    std::io::stdout().flush()?;
+    environment.runtime.exit_status = Some(status);
+    Err(CreateError { code: 19, message: format!("Program exited with status {}", status) })
+}
+
+pub fn throw_value(values: Vec<CreateAny>, environment: &mut Environment, _: bool) -> Result<CreateAny, CreateError> {
+    let value = values.into_iter().next().unwrap();
+    let message = match read_text("throw", &value) {
+        Ok(text) => text,
+        Err(_) => value.to_string(),
+    };
+    environment.runtime.thrown = Some(value);
+    Err(CreateError { code: 25, message })
+}
@@ -1,4 +1,5 @@
 use regex::Regex;
+use std::sync::OnceLock;
 use super::errors;
 use super::interpreter::{Identifier, CreateType};
 use super::utils::*;
@@ -28,14 +29,81 @@ pub enum Command {
     ATN,
     SQT,
     CBT,
+    SNH,
+    CSH,
+    TNH,
+    ASH,
+    ACH,
+    ATH,
+    AT2,
+    HYP,
+    LGN,
+    LGT,
+    LGB,
+    EXP,
+    ABS,
+    FLR,
+    CIL,
+    RND,
+    TRC,
+    SGN,
+    MIN,
+    MAX,
+    CLP,
+    BND,
+    BOR,
+    BXR,
+    BNT,
+    SHL,
+    SHR,
+    IDV,
+    EMD,
+    LEN,
+    PSH,
+    PSF,
+    POP,
+    POF,
+    INS,
+    REM,
+    SLC,
+    CCT,
+    REV,
+    IDX,
+    CON,
+    SRT,
+    MAP,
+    FLT,
+    FLD,
+    RDC,
+    ANY,
+    ALL,
+    FND,
+    SRB,
+    RNG,
     EQU,
     NOT,
     GTH,
     LTH,
-    ORR,
-    AND,
+    NEQ,
+    GTE,
+    LTE,
+    XOR,
     PNT,
     PTC,
+    PRV,
+    PRL,
+    PRT,
+    FMT,
+    PRF,
+    ENV,
+    EXT,
+    THW,
+    RFL,
+    RFS,
+    WFL,
+    AFL,
+    EXS,
+    LSD,
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +118,9 @@ pub enum Special {
     SGA(Identifier),
     SLB(Identifier),
     SLA(Identifier),
+    SIA(Identifier),
+    SGI(Identifier),
+    SLI(Identifier),
     GIA(Identifier),
     GNB(Identifier),
     FNC(Identifier),
@@ -61,6 +132,13 @@ pub enum Special {
     OPR(),
     CLR(),
     PIP(),
+    RNM(),
+    RCH(),
+    RLN(),
+    RAL(),
+    EOF(),
+    IMP(String),
+    LBL(String),
 }
 
 #[derive(Debug, Clone)]
@@ -71,7 +149,29 @@ pub enum ControlFlow {
     FRN,
     WHL,
     BRK,
+    CNT,
     RTN,
+    ORR,
+    AND,
+    FRM,
+    STP,
+    MCH,
+    UPT,
+    EXH,
+    TRY,
+    CAT,
+    FIN,
+}
+
+fn compile(pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap()
+}
+
+macro_rules! pattern {
+    ($re:literal) => {{
+        static PATTERN: OnceLock<Regex> = OnceLock::new();
+        PATTERN.get_or_init(|| compile($re))
+    }};
 }
 
 pub fn tokenize(data: &str) -> Result<Vec<Token>, errors::CreateError> {
@@ -87,6 +187,21 @@ pub fn tokenize(data: &str) -> Result<Vec<Token>, errors::CreateError> {
             use Special::*;
             use ControlFlow::*;
             use std::f32::consts::*;
+            if raw_token == "import" {
+                let path = match raw_tokens.pop() {
+                    Some(p) if p.starts_with('"') => {
+                        let mut chars = p[1..].chars().peekable();
+                        let mut path = String::new();
+                        while let Some(_) = chars.peek() {
+                            path.extend(char::from_u32(read_char(&mut chars)? as u32));
+                        }
+                        path
+                    },
+                    _ => return Err(errors::CreateError{ code: 2, message: format!("Expected a string path after import at line {}, char {}", line, chr) }),
+                };
+                tokens.push(SPC(IMP(path)));
+                continue;
+            }
             if &raw_token[0..1] == "\"" {
                 let mut chars = raw_token[1..].chars().peekable();
                 while let Some(_) = chars.peek() {
@@ -109,28 +224,125 @@ pub fn tokenize(data: &str) -> Result<Vec<Token>, errors::CreateError> {
                 "asin" => CMD(ASN),
                 "acos" => CMD(ACS),
                 "atan" => CMD(ATN),
+                "sinh" => CMD(SNH),
+                "cosh" => CMD(CSH),
+                "tanh" => CMD(TNH),
+                "asinh" => CMD(ASH),
+                "acosh" => CMD(ACH),
+                "atanh" => CMD(ATH),
+                "atan2" => CMD(AT2),
+                "hypot" => CMD(HYP),
                 // Roots
                 "sqrt" => CMD(SQT),
                 "cbrt" => CMD(CBT),
+                // Logarithms
+                "ln" => CMD(LGN),
+                "log10" => CMD(LGT),
+                "log2" => CMD(LGB),
+                "exp" => CMD(EXP),
+                // Rounding
+                "abs" => CMD(ABS),
+                "floor" => CMD(FLR),
+                "ceil" => CMD(CIL),
+                "round" => CMD(RND),
+                "trunc" => CMD(TRC),
+                "sign" => CMD(SGN),
+                // Comparison
+                "min" => CMD(MIN),
+                "max" => CMD(MAX),
+                "clamp" => CMD(CLP),
+                // Integer
+                "band" => CMD(BND),
+                "bor" => CMD(BOR),
+                "bxor" => CMD(BXR),
+                "bnot" => CMD(BNT),
+                "shl" => CMD(SHL),
+                "shr" => CMD(SHR),
+                "idiv" => CMD(IDV),
+                "emod" => CMD(EMD),
+                // Arrays
+                "len" => CMD(LEN),
+                "push" => CMD(PSH),
+                "pushf" => CMD(PSF),
+                "pop" => CMD(POP),
+                "popf" => CMD(POF),
+                "insert" => CMD(INS),
+                "remove" => CMD(REM),
+                "slice" => CMD(SLC),
+                "concat" => CMD(CCT),
+                "reverse" => CMD(REV),
+                "indexof" => CMD(IDX),
+                "contains" => CMD(CON),
+                "sort" => CMD(SRT),
+                "map" => CMD(MAP),
+                "filter" => CMD(FLT),
+                "fold" => CMD(FLD),
+                "reduce" => CMD(RDC),
+                "any" => CMD(ANY),
+                "all" => CMD(ALL),
+                "find" => CMD(FND),
+                "sortby" => CMD(SRB),
+                "range" => CMD(RNG),
                 // Constants
                 "pi" => NUM(PI),
+                "e" => NUM(E),
+                "tau" => NUM(TAU),
+                "inf" => NUM(f32::INFINITY),
+                "nan" => NUM(f32::NAN),
+                "true" => NUM(1.),
+                "false" => NUM(0.),
                 // Boolean
                 "==" => CMD(EQU),
                 "!" => CMD(NOT),
                 ">" => CMD(GTH),
                 "<" => CMD(LTH),
-                "||" => CMD(ORR),
-                "&&" => CMD(AND),
+                "!=" => CMD(NEQ),
+                ">=" => CMD(GTE),
+                "<=" => CMD(LTE),
+                "xor" => CMD(XOR),
+                "||" => CFL(ORR),
+                "&&" => CFL(AND),
                 // Output
                 "." => CMD(PNT),
                 "," => CMD(PTC),
+                "print" => CMD(PRV),
+                "println" => CMD(PRL),
+                "prints" => CMD(PRT),
+                "format" => CMD(FMT),
+                "printf" => CMD(PRF),
+                // Input
+                "readnum" => SPC(RNM()),
+                "readchar" => SPC(RCH()),
+                "readline" => SPC(RLN()),
+                "readall" => SPC(RAL()),
+                "eof" => SPC(EOF()),
+                // System
+                "getenv" => CMD(ENV),
+                "exit" => CMD(EXT),
+                "throw" => CMD(THW),
+                // Files
+                "readfile" => CMD(RFL),
+                "readlines" => CMD(RFS),
+                "writefile" => CMD(WFL),
+                "appendfile" => CMD(AFL),
+                "exists" => CMD(EXS),
+                "listdir" => CMD(LSD),
                 // Control Flow
                 "if" => CFL(IFF),
                 "else" => CFL(ELS),
                 "for" => CFL(FOR),
                 "forin" => CFL(FRN),
+                "from" => CFL(FRM),
+                "step" => CFL(STP),
                 "while" => CFL(WHL),
+                "match" => CFL(MCH),
+                "to" => CFL(UPT),
+                "exhaustive" => CFL(EXH),
+                "try" => CFL(TRY),
+                "catch" => CFL(CAT),
+                "finally" => CFL(FIN),
                 "break" => CFL(BRK),
+                "continue" => CFL(CNT),
                 "return" => CFL(RTN),
                 // Scoping
                 "{" => SPC(OPB()),
@@ -153,18 +365,18 @@ pub fn tokenize(data: &str) -> Result<Vec<Token>, errors::CreateError> {
                 "scp" => TYP(CreateType::SCP),
                 "non" => TYP(CreateType::NUL),
                 // Fancy
-                _ => match raw_token.parse::<f32>() {
-                    Ok(v) => NUM(v),
-                    Err(_) => { // not a valid number
+                _ => match read_number(raw_token) {
+                    Some(v) => NUM(v),
+                    None => { // not a valid number
                         match raw_token.chars().nth(0).unwrap() {
                             '~' => match raw_token[1..].parse::<usize>() {
                                 Ok(v) => SPC(IBF(v)),
                                 Err(_) => {
-                                    if Regex::new(r"^\w+(\.\w+)*$").unwrap().is_match(&raw_token[1..]) {
+                                    if pattern!(r"^\w+(\.\w+)*$").is_match(&raw_token[1..]) {
                                         SPC(GNB(raw_token[1..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
-                                    } else if Regex::new(r"^\w+(\.\w+)*\[$").unwrap().is_match(&raw_token[1..]) {
+                                    } else if pattern!(r"^\w+(\.\w+)*\[$").is_match(&raw_token[1..]) {
                                         SPC(GIA(raw_token[1..(raw_token.len()-1)].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
-                                    } else if Regex::new(r"^\w+(\.\w+)*\($").unwrap().is_match(&raw_token[1..]) {
+                                    } else if pattern!(r"^\w+(\.\w+)*\($").is_match(&raw_token[1..]) {
                                         SPC(FNC(raw_token[1..(raw_token.len()-1)].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
                                     } else {
                                         return Err(errors::CreateError{ code: 2, message: format!("Could not read name or index of buffer at line {}, char {}", line, chr) });
@@ -172,36 +384,49 @@ pub fn tokenize(data: &str) -> Result<Vec<Token>, errors::CreateError> {
                                 }
                             },
                             '=' => {
-                                if Regex::new(r"^\w+(\.\w+)*$").unwrap().is_match(&raw_token[1..]) {
+                                if pattern!(r"^\w+(\.\w+)*$").is_match(&raw_token[1..]) {
                                     SPC(SNB(raw_token[1..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
-                                } else if Regex::new(r"^\[\]\w+(\.\w+)*$").unwrap().is_match(&raw_token[1..]) {
+                                } else if pattern!(r"^\[\]\w+(\.\w+)*$").is_match(&raw_token[1..]) {
                                     SPC(SNA(raw_token[3..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
-                                } else if Regex::new(r"^\(\)\w+(\.\w+)*$").unwrap().is_match(&raw_token[1..]) {
+                                } else if pattern!(r"^\(\)\w+(\.\w+)*$").is_match(&raw_token[1..]) {
                                     SPC(SNF(raw_token[3..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
-                                } else if Regex::new(r"^\|\|\w+(\.\w+)*$").unwrap().is_match(&raw_token[1..]) {
+                                } else if pattern!(r"^\|\|\w+(\.\w+)*$").is_match(&raw_token[1..]) {
                                     SPC(SNS(raw_token[3..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
+                                } else if pattern!(r"^\w+(\.\w+)*\[$").is_match(&raw_token[1..]) {
+                                    SPC(SIA(raw_token[1..(raw_token.len()-1)].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
                                 } else {
                                     return Err(errors::CreateError{ code: 2, message: format!("Invalid name for setting a named buffer at line {}, char {}", line, chr)})
                                 }
                             },
                             'g' => {
-                                if Regex::new(r"^=\w+$").unwrap().is_match(&raw_token[1..]) {
+                                if pattern!(r"^=\w+$").is_match(&raw_token[1..]) {
                                     SPC(SGB(raw_token[2..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
-                                } else if Regex::new(r"^=\[\]\w+(\.\w+)*$").unwrap().is_match(&raw_token[1..]) {
+                                } else if pattern!(r"^=\[\]\w+(\.\w+)*$").is_match(&raw_token[1..]) {
                                     SPC(SGA(raw_token[4..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
+                                } else if pattern!(r"^=\w+(\.\w+)*\[$").is_match(&raw_token[1..]) {
+                                    SPC(SGI(raw_token[2..(raw_token.len()-1)].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
                                 } else {
                                     return Err(errors::CreateError{ code: 2, message: format!("Invalid token {} at line {}, char {}", raw_token, line, chr)})
                                 }
                             },
                             'l' => {
-                                if Regex::new(r"^=\w+(\.\w+)*$").unwrap().is_match(&raw_token[1..]) {
+                                if pattern!(r"^=\w+(\.\w+)*$").is_match(&raw_token[1..]) {
                                     SPC(SLB(raw_token[2..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
-                                } else if Regex::new(r"^=\[\]\w+(\.\w+)*$").unwrap().is_match(&raw_token[1..]) {
+                                } else if pattern!(r"^=\[\]\w+(\.\w+)*$").is_match(&raw_token[1..]) {
                                     SPC(SLA(raw_token[4..].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
+                                } else if pattern!(r"^=\w+(\.\w+)*\[$").is_match(&raw_token[1..]) {
+                                    SPC(SLI(raw_token[2..(raw_token.len()-1)].split('.').map(|x| x.to_string()).rev().collect::<Identifier>()))
                                 } else {
                                     return Err(errors::CreateError{ code: 2, message: format!("Invalid token {} at line {}, char {}", raw_token, line, chr)})
                                 }
                             },
+                            '@' => {
+                                if pattern!(r"^\w+$").is_match(&raw_token[1..]) {
+                                    SPC(LBL(raw_token[1..].to_string()))
+                                } else {
+                                    return Err(errors::CreateError{ code: 2, message: format!("Invalid label {} at line {}, char {}", raw_token, line, chr)})
+                                }
+                            },
                             '\'' => {
                                 let mut chars = raw_token[1..].chars();
                                 let val = NUM(read_char(&mut chars)? as f32);
@@ -3,36 +3,36 @@ use super::functions::*;
 use super::interpreter::*;
 use super::errors::*;
 
-pub fn read_scope(tokens: &mut Vec<Token>) -> Result<ScopePrototype, CreateError> {
+pub fn read_scope(tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<ScopePrototype, CreateError> {
     use Token::*;
     use Special::*;
     let mut scope = ScopePrototype::new();
     while let Some(token) = tokens.pop() {
         match token {
             SPC(SNB(mut n)) => {
-                if n.len() > 1 {return Err(CreateError { code: 3, message: "Scopes can only have single level value names".to_string() })}
-                scope.insert(n.pop().unwrap(), read_mutable_buffer(tokens, None)?, CreateType::BUF);
+                if n.len() > 1 {return Err(CreateError { code: 2, message: "Scopes can only have single level value names".to_string() })}
+                scope.insert(n.pop().unwrap(), read_mutable_buffer(tokens, None, context)?, CreateType::BUF);
             },
             SPC(SNA(mut n)) => {
-                if n.len() > 1 {return Err(CreateError { code: 3, message: "Scopes can only have single level value names".to_string() })}
-                scope.insert(n.pop().unwrap(), read_mutable_buffer(tokens, None)?, CreateType::ARR);
+                if n.len() > 1 {return Err(CreateError { code: 2, message: "Scopes can only have single level value names".to_string() })}
+                scope.insert(n.pop().unwrap(), read_mutable_buffer(tokens, None, context)?, CreateType::ARR);
             },
             SPC(SNF(mut n)) => {
-                if n.len() > 1 {return Err(CreateError { code: 3, message: "Scopes can only have single level value names".to_string() })}
-                scope.insert(n.pop().unwrap(), read_mutable_buffer(tokens, None)?, CreateType::FUN);
+                if n.len() > 1 {return Err(CreateError { code: 2, message: "Scopes can only have single level value names".to_string() })}
+                scope.insert(n.pop().unwrap(), read_mutable_buffer(tokens, None, context)?, CreateType::FUN);
             },
             SPC(SNS(mut n)) => {
-                if n.len() > 1 {return Err(CreateError { code: 3, message: "Scopes can only have single level value names".to_string() })}
-                scope.insert(n.pop().unwrap(), read_mutable_buffer(tokens, None)?, CreateType::SCP);
+                if n.len() > 1 {return Err(CreateError { code: 2, message: "Scopes can only have single level value names".to_string() })}
+                scope.insert(n.pop().unwrap(), read_mutable_buffer(tokens, None, context)?, CreateType::SCP);
             }, 
             SPC(PIP()) => break,
-            _ => return Err(CreateError { code: 3, message: "Expected setter or closing pipe in scope declaration".to_string() }),
+            _ => return Err(CreateError { code: 2, message: "Expected setter or closing pipe in scope declaration".to_string() }),
         }
     }
     Ok(scope)
 }
 
-pub fn read_function(tokens: &mut Vec<Token>) -> Result<Function, CreateError> {
+pub fn read_function(tokens: &mut Vec<Token>, context: &mut ParseContext) -> Result<Function, CreateError> {
     use Token::*;
     use Special::*;
     let mut params: Vec<(CreateType, Identifier)> = Vec::new();
@@ -43,22 +43,29 @@ pub fn read_function(tokens: &mut Vec<Token>) -> Result<Function, CreateError> {
             SPC(SNA(n)) => params.push((CreateType::ARR, n)),                            
             SPC(SNS(n)) => params.push((CreateType::SCP, n)),
             SPC(SNF(n)) => params.push((CreateType::FUN, n)),
-            _ => return Err(CreateError { code: 3, message: "Function arguments must be a form of setter".to_string() }),
+            _ => return Err(CreateError { code: 2, message: "Function arguments must be a form of setter".to_string() }),
         }                   
     }
     let filtered_params = params.into_iter()
         .map(|mut i| {
             Ok((i.0, {
-                    if i.1.len() > 1 {return Err(CreateError { code: 3, message: "Function arguments can only be first layer names".to_string() })}
+                    if i.1.len() > 1 {return Err(CreateError { code: 2, message: "Function arguments can only be first layer names".to_string() })}
                     else {i.1.pop().unwrap()}
             }))
         })
         .collect::<Result<Vec<(CreateType, String)>, CreateError>>()?;
-    let return_type = match tokens.pop().ok_or(CreateError { code: 2, message: "Expected a return type in function declaration".to_string() })? {
-        TYP(t) => t,
-        _ => return Err(CreateError { code: 3, message: "Expected type statement after function declaration".to_string() }),
-    };
-    let fun = Function::new(filtered_params, read_mutable_buffer(tokens, None)?, return_type);
+    let mut return_types = Vec::new();
+    while let Some(TYP(t)) = tokens.last() {
+        return_types.push(t.clone());
+        tokens.pop();
+    }
+    match tokens.last() {
+        None if return_types.is_empty() => return Err(CreateError { code: 2, message: "Expected a return type in function declaration".to_string() }),
+        _ if return_types.is_empty() => return Err(CreateError { code: 2, message: "Expected type statement after function declaration".to_string() }),
+        _ => (),
+    }
+    let return_count = if let [CreateType::NUL] = return_types[..] {0} else {return_types.len()};
+    let fun = Function::new(filtered_params, read_function_body(tokens, return_count, context)?, return_types);
     Ok(fun)
 }
 
@@ -77,3 +84,60 @@ pub fn read_char(chars: &mut impl Iterator<Item = char>) -> Result<usize, Create
         c => Ok(c as usize)
     }
 }
+
+pub fn read_number(raw: &str) -> Option<f32> {
+    let (negative, body) = match raw.strip_prefix('-') {
+        Some(b) => (true, b),
+        None => (false, raw),
+    };
+    if !body.starts_with(|c: char| c.is_ascii_digit()) {
+        return raw.parse::<f32>().ok();
+    }
+    if body.ends_with('_') || body.contains("__") {return None}
+    let digits = body.replace('_', "");
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).ok()? as f32
+    } else if let Some(bin) = digits.strip_prefix("0b") {
+        u64::from_str_radix(bin, 2).ok()? as f32
+    } else if let Some(oct) = digits.strip_prefix("0o") {
+        u64::from_str_radix(oct, 8).ok()? as f32
+    } else {
+        digits.parse::<f32>().ok()?
+    };
+    Some(if negative {-value} else {value})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_decimal_numbers() {
+        assert_eq!(read_number("42"), Some(42.));
+        assert_eq!(read_number("-2.5"), Some(-2.5));
+        assert_eq!(read_number("1e3"), Some(1000.));
+    }
+
+    #[test]
+    fn reads_radix_prefixes() {
+        assert_eq!(read_number("0xff"), Some(255.));
+        assert_eq!(read_number("0b101"), Some(5.));
+        assert_eq!(read_number("0o17"), Some(15.));
+        assert_eq!(read_number("-0x10"), Some(-16.));
+    }
+
+    #[test]
+    fn reads_digit_separators() {
+        assert_eq!(read_number("1_000_000"), Some(1000000.));
+        assert_eq!(read_number("0b1111_0000"), Some(240.));
+        assert_eq!(read_number("1_000_"), None);
+        assert_eq!(read_number("1__000"), None);
+    }
+
+    #[test]
+    fn rejects_non_numbers() {
+        assert_eq!(read_number("abc"), None);
+        assert_eq!(read_number("0xg"), None);
+        assert_eq!(read_number("-"), None);
+    }
+}
@@ -1,41 +1,82 @@
 use std::fs::File;
-use std::io::{BufRead,BufReader};
+use std::io::{BufRead,BufReader,Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
 use clap::Parser;
 
 mod lib;
 use crate::lib::errors::*;
 use crate::lib::tokenizer::*;
 use crate::lib::interpreter::*;
+use crate::lib::runtime::*;
+use crate::lib::files::{allow_path, resolve_path};
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
+#[clap(trailing_var_arg = true)]
+#[clap(after_help = "EXIT STATUS:\n    0    The program finished, or called exit 0\n    1    A runtime error was thrown\n    2    The source contained a syntax error\n    3    A file or input could not be accessed\n    The exit instruction may set any status from 0 to 255.")]
 struct Args {
     filepath: String,
 
     #[clap(short, long)]
     debug: bool,
+
+    #[clap(long)]
+    strict_math: bool,
+
+    #[clap(long)]
+    no_prelude: bool,
+
+    #[clap(long, value_name = "DIR", multiple_occurrences = true, multiple_values = false)]
+    allow_read: Vec<String>,
+
+    #[clap(long, value_name = "DIR", multiple_occurrences = true, multiple_values = false)]
+    allow_write: Vec<String>,
+
+    #[clap(long, value_name = "DIR", multiple_occurrences = true, multiple_values = false)]
+    import_path: Vec<String>,
+
+    #[clap(multiple_values = true, allow_hyphen_values = true)]
+    args: Vec<String>,
 }
 
-fn main() -> Result<(), CreateError> {
+fn main() -> ExitCode {
     let args = Args::parse();
 
     let tokens = match read_file(args.filepath.as_str()) {
-        Err(e) => {
-            if args.debug {println!("{:?}", e)} else {println!("{}", e)}
-            return Err(CreateError { code: 2, message: "Could not read file properly.".to_string() });
+        Err(e) => return report(e, args.debug),
+        Ok(d) => match tokenize(d.as_str()) {
+            Ok(t) => t,
+            Err(e) => return report(e, args.debug),
         },
-        Ok(d) => tokenize(d.as_str())?,
     };
 
-    match interpret_program(tokens) {
-        CreateResult::Ok() => (),
-        CreateResult::Err(e) => {
-            if args.debug {println!("{:?}", e)} else {println!("{}", e)}
-            return Err(e);
-        },
+    let mut runtime = Runtime::new();
+    runtime.strict_math = args.strict_math;
+    runtime.prelude = !args.no_prelude;
+    runtime.args = args.args;
+    runtime.import_stack.push(resolve_path(args.filepath.as_str()).unwrap_or_else(|| PathBuf::from(&args.filepath)));
+    for (dirs, allowed) in [(&args.allow_read, &mut runtime.allow_read), (&args.allow_write, &mut runtime.allow_write), (&args.import_path, &mut runtime.import_paths)] {
+        for dir in dirs {
+            match allow_path(dir) {
+                Ok(p) => allowed.push(p),
+                Err(e) => return report(e, args.debug),
+            }
+        }
     }
 
-    return Ok(());
+    let result = interpret_program(tokens, &mut runtime);
+    let _ = std::io::stdout().flush();
+    match (result, runtime.exit_status) {
+        (_, Some(status)) => ExitCode::from(status),
+        (CreateResult::Ok(), None) => ExitCode::SUCCESS,
+        (CreateResult::Err(e), None) => report(e, args.debug),
+    }
+}
+
+fn report(e: CreateError, debug: bool) -> ExitCode {
+    if debug {eprintln!("{:?}", e)} else {eprintln!("{}", e)}
+    ExitCode::from(e.exit_status())
 }
 
 fn read_file(file: &str) -> Result<String,CreateError> {